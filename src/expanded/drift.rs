@@ -0,0 +1,230 @@
+use serde::Serialize;
+
+use super::{ContentType, Expanded, ExpandedMpd, MediaOffset, MediaTime};
+use crate::{push_fmt, push_line};
+
+/// Used unless another threshold is configured, about a frame at 25fps
+pub const DEFAULT_DRIFT_THRESHOLD_MS: u64 = 40;
+
+/// Where every AdaptationSet of a Period ends against the start of the next Period
+#[derive(Debug, Serialize)]
+pub struct BoundaryDrift {
+    /// Index of the Period ending at the boundary
+    pub period_index: usize,
+    pub period_id: String,
+    pub next_period_id: String,
+    pub next_period_start: MediaTime,
+    pub adaptation_sets: Vec<AdaptationSetDrift>,
+    /// Between the earliest and latest end of the audio and video AdaptationSets
+    pub misalignment: MediaTime,
+    /// Whether `misalignment` is above the threshold
+    pub flagged: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdaptationSetDrift {
+    pub id: String,
+    pub content_type: ContentType,
+    pub lang: Option<String>,
+    pub end: MediaTime,
+    /// How far the AdaptationSet ends past the start of the next Period, negative when it ends
+    /// before it
+    pub offset: MediaOffset,
+    /// The sum of `offset` of this content type and language over every boundary so far, drift
+    /// that builds up over many ad breaks
+    pub cumulative_offset: MediaOffset,
+}
+
+impl ExpandedMpd {
+    /// The drift at every boundary between two Periods. Boundaries where audio and video end
+    /// further apart than `threshold` are flagged.
+    pub fn boundary_drift(&self, threshold: MediaTime) -> Vec<BoundaryDrift> {
+        // Running offsets by content type and language
+        let mut cumulative: Vec<(ContentType, Option<String>, MediaOffset)> = vec![];
+
+        self.periods
+            .windows(2)
+            .enumerate()
+            .map(|(period_index, pair)| {
+                let (period, next_period) = (&pair[0], &pair[1]);
+
+                let next_period_start = next_period.period_start;
+
+                let adaptation_sets: Vec<AdaptationSetDrift> = period
+                    .adaptation_sets
+                    .iter()
+                    .map(|adaptation_set| {
+                        let end = adaptation_set.end();
+
+                        let offset = MediaOffset::between(next_period_start, end);
+
+                        let total = match cumulative.iter_mut().find(|(content_type, lang, _)| {
+                            *content_type == adaptation_set.content_type
+                                && *lang == adaptation_set.lang
+                        }) {
+                            Some((_, _, total)) => {
+                                *total = *total + offset;
+                                *total
+                            }
+                            None => {
+                                cumulative.push((
+                                    adaptation_set.content_type,
+                                    adaptation_set.lang.clone(),
+                                    offset,
+                                ));
+                                offset
+                            }
+                        };
+
+                        AdaptationSetDrift {
+                            id: adaptation_set.id.clone(),
+                            content_type: adaptation_set.content_type,
+                            lang: adaptation_set.lang.clone(),
+                            end,
+                            offset,
+                            cumulative_offset: total,
+                        }
+                    })
+                    .collect();
+
+                let ends = || {
+                    adaptation_sets
+                        .iter()
+                        .filter(|drift| {
+                            matches!(drift.content_type, ContentType::Audio | ContentType::Video)
+                        })
+                        .map(|drift| drift.end)
+                };
+
+                let misalignment = match (ends().min(), ends().max()) {
+                    (Some(earliest), Some(latest)) => latest.saturating_sub(earliest),
+                    _ => MediaTime::ZERO,
+                };
+
+                BoundaryDrift {
+                    period_index,
+                    period_id: period.id.clone(),
+                    next_period_id: next_period.id.clone(),
+                    next_period_start,
+                    adaptation_sets,
+                    misalignment,
+                    flagged: misalignment > threshold,
+                }
+            })
+            .collect()
+    }
+
+    /// A plain text report of `boundary_drift`, a line per boundary and per AdaptationSet
+    pub fn describe_drift(&self, threshold: MediaTime) -> String {
+        let mut text = String::new();
+
+        for boundary in self.boundary_drift(threshold) {
+            push_fmt!(
+                text,
+                "Period {} to {} at {}, audio and video end {} apart",
+                boundary.period_id,
+                boundary.next_period_id,
+                boundary.next_period_start,
+                boundary.misalignment
+            );
+
+            if boundary.flagged {
+                push_fmt!(text, ", drift above {}", threshold);
+            }
+
+            push_line!(text);
+
+            for drift in boundary.adaptation_sets.iter() {
+                push_fmt!(
+                    text,
+                    "  AdaptationSet {} {}{} ends {}, {} vs the next Period",
+                    drift.id,
+                    drift.content_type.as_str(),
+                    drift
+                        .lang
+                        .as_ref()
+                        .map(|lang| format!(" {}", lang))
+                        .unwrap_or_default(),
+                    drift.end,
+                    drift.offset
+                );
+
+                if drift.cumulative_offset != drift.offset {
+                    push_fmt!(text, ", {} in total", drift.cumulative_offset);
+                }
+
+                push_line!(text);
+            }
+        }
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        expanded::{MediaOffset, MediaTime, ParseMode},
+        util::fixture::{manifest, Manifest},
+    };
+
+    const VIDEO: &str =
+        r#"<SegmentTemplate timescale="1000" duration="2000" media="$Number$.m4s"/>"#;
+
+    /// Ends a tick of 48kHz past 6.04s
+    const AUDIO: &str = r#"<AdaptationSet id="2" contentType="audio" mimeType="audio/mp4" codecs="mp4a.40.2" audioSamplingRate="48000">
+      <SegmentTemplate timescale="48000" media="$Time$.m4s">
+        <SegmentTimeline><S t="0" d="96000" r="1"/><S d="97921"/></SegmentTimeline>
+      </SegmentTemplate>
+      <Representation id="a1" bandwidth="128000"/>
+    </AdaptationSet>"#;
+
+    fn period(manifest: Manifest) -> Manifest {
+        manifest
+            .period_attribute("duration", "PT6S")
+            .period_child(AUDIO)
+            .adaptation_set_child(VIDEO)
+    }
+
+    #[test]
+    fn offsets_add_up_exactly() {
+        let manifest = manifest().mpd_attribute("mediaPresentationDuration", "PT18S");
+        let manifest = period(period(period(manifest).period("p1", "PT6S")).period("p2", "PT12S"));
+
+        let mpd = manifest.expand(ParseMode::Default).unwrap();
+
+        let boundaries = mpd.boundary_drift(MediaTime::from_ms(40));
+
+        let [first, second] = &boundaries[..] else {
+            panic!("Expecting two boundaries, got {:?}", boundaries);
+        };
+
+        let audio_offset = MediaOffset::between(MediaTime::ZERO, MediaTime::new(1921, 48000));
+
+        for boundary in [first, second] {
+            let [audio, video] = &boundary.adaptation_sets[..] else {
+                panic!("Expecting audio and video");
+            };
+
+            assert_eq!(audio.offset, audio_offset);
+            assert_eq!(
+                video.offset,
+                MediaOffset::between(MediaTime::ZERO, MediaTime::ZERO)
+            );
+            assert!(boundary.flagged);
+        }
+
+        // 80.04ms, where adding milliseconds would give 80ms
+        assert_eq!(
+            second.adaptation_sets[0].cumulative_offset,
+            audio_offset + audio_offset
+        );
+        assert_eq!(
+            second.adaptation_sets[0].cumulative_offset.magnitude,
+            MediaTime::new(3842, 48000)
+        );
+        assert!(mpd
+            .describe_drift(MediaTime::from_ms(40))
+            .contains("ends 12.040s, +0.040s vs the next Period, +0.080s in total"));
+    }
+}
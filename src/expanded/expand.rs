@@ -1,99 +1,589 @@
+use std::path::Path;
+
 use dash_mpd::MPD;
 
 use crate::{
     debug,
     util::{
-        error::ParseError,
+        error::{ManifestLocation, ParseError, ParseWarning},
         parse::{
-            describe_representation, parse_period_duration_ms, parse_period_start_ms,
-            parse_segment_template,
+            describe_representation, parse_content_type, parse_live, parse_period_duration,
+            parse_period_end, parse_period_start, parse_segment_base, parse_segment_list,
+            parse_segment_template, segment_base_from_source, segment_list_from_source,
+            source_attribute, summarise_representation,
         },
+        source::{find_element, SourceElement},
     },
 };
 
-use super::{Expanded, ExpandedAdaptationSet, ExpandedMpd, ExpandedPeriod, ExpandedRepresentation};
+use super::{
+    Expanded, ExpandedAdaptationSet, ExpandedMpd, ExpandedPeriod, ExpandedRepresentation,
+    MediaTime, ParseMode,
+};
 
 impl ExpandedMpd {
-    pub fn new(mpd: MPD) -> Self {
+    /// Expands every Period of `mpd`. `source` is the scanned manifest `mpd` was parsed from, for
+    /// the attributes dash-mpd does not parse. When `media_dir` is provided, SegmentBase
+    /// Representations are resolved against local media files in that directory to read their
+    /// sidx.
+    ///
+    /// Fallbacks taken while expanding are collected in `warnings`. In strict mode the first
+    /// warning fails the expansion, in lenient mode Representations that cannot be expanded are
+    /// skipped and reported as warnings instead of failing the whole manifest.
+    pub fn new(
+        mpd: MPD,
+        source: &[SourceElement],
+        media_dir: Option<&Path>,
+        mode: ParseMode,
+    ) -> Result<Self, ParseError> {
         let mut _periods: Vec<ExpandedPeriod> = vec![];
+        let mut warnings: Vec<ParseWarning> = vec![];
+
+        if mpd.periods.is_empty() {
+            return Err(ParseError::MpdWithoutPeriods);
+        }
 
-        let mut previous_period_end_ms = 0u64;
+        let mut previous_period_end: Option<MediaTime> = None;
 
-        for p in mpd.periods {
+        let live = parse_live(&mpd)?;
+
+        let live_window = live
+            .as_ref()
+            .map(|live| (live.window_start, live.live_edge));
+
+        for (index, p) in mpd.periods.iter().enumerate() {
             let period_id = p.id.clone().unwrap_or("No ID".to_owned());
 
             debug!("\nPeriod: {}", period_id);
 
-            let period_start_ms = parse_period_start_ms(&p, previous_period_end_ms);
-            let period_duration_ms: Option<u64> = parse_period_duration_ms(&p);
+            let period_location = ManifestLocation::period(&period_id, index);
+
+            let source_period = find_element(source, "Period", &period_id, index);
+
+            // dash-mpd only parses SegmentBase on the Representation and SegmentList up to the
+            // AdaptationSet
+            let period_segment_base = segment_base_from_source(source_period);
+            let period_segment_list = segment_list_from_source(source_period);
+
+            let mut period_warnings: Vec<ParseWarning> = vec![];
+
+            if p.id.is_none() {
+                period_warnings.push(ParseWarning::PeriodWithoutId);
+            }
+
+            let period_start = parse_period_start(p, previous_period_end, &mut period_warnings)
+                .map_err(|err| err.at(&period_location))?;
+
+            collect_warnings(period_warnings, &period_location, mode, &mut warnings)?;
+
+            let period_duration: Option<MediaTime> =
+                parse_period_duration(p).map_err(|err| err.at(&period_location))?;
+            let period_end = parse_period_end(
+                period_start,
+                period_duration,
+                mpd.periods.get(index + 1),
+                mpd.mediaPresentationDuration,
+            );
 
             debug!("  {} AdaptationSets", p.adaptations.len());
 
+            if p.adaptations.is_empty() {
+                let err = ParseError::PeriodWithoutAdaptationSets.at(&period_location);
+
+                skip_or_fail(err, mode, &mut warnings)?;
+                continue;
+            }
+
             let mut adaptation_sets: Vec<ExpandedAdaptationSet> = vec![];
 
-            for adaptation in p.adaptations.iter() {
+            for (adaptation_index, adaptation) in p.adaptations.iter().enumerate() {
                 let adaptation_set_id = adaptation.id.clone().unwrap_or("No ID".to_owned());
 
+                let source_adaptation_set = source_period.and_then(|period| {
+                    period.child("AdaptationSet", &adaptation_set_id, adaptation_index)
+                });
+
+                let adaptation_segment_base = segment_base_from_source(source_adaptation_set);
+
+                let adaptation_set_location =
+                    period_location.adaptation_set(&adaptation_set_id, adaptation_index);
+
+                let mut adaptation_set_warnings: Vec<ParseWarning> = vec![];
+
+                let content_type =
+                    match parse_content_type(adaptation, &mut adaptation_set_warnings) {
+                        Ok(content_type) => content_type,
+                        Err(err) => {
+                            skip_or_fail(err.at(&adaptation_set_location), mode, &mut warnings)?;
+                            continue;
+                        }
+                    };
+
+                collect_warnings(
+                    adaptation_set_warnings,
+                    &adaptation_set_location,
+                    mode,
+                    &mut warnings,
+                )?;
+
+                if adaptation.representations.is_empty() {
+                    let err = ParseError::AdaptationSetWithoutRepresentations
+                        .at(&adaptation_set_location);
+
+                    skip_or_fail(err, mode, &mut warnings)?;
+                    continue;
+                }
+
                 debug!(
                     "\n  AdaptationSet {} ({}) has {} Representations",
                     adaptation_set_id,
-                    adaptation
-                        .contentType
-                        .clone()
-                        .unwrap_or("No contentType".to_owned()),
+                    content_type.as_str(),
                     adaptation.representations.len()
                 );
 
                 let mut representations: Vec<ExpandedRepresentation> = vec![];
 
-                let content_type = match adaptation.contentType {
-                    Some(ref s) if s == "audio" => "audio",
-                    Some(ref s) if s == "video" => "video",
-                    _ => panic!(
-                        "{}",
-                        &ParseError::AdaptationSetWithoutContentType.describe()
-                    ),
-                };
-
-                for rep in adaptation.representations.iter() {
+                for (representation_index, rep) in adaptation.representations.iter().enumerate() {
                     let representation_id = rep.id.clone().unwrap_or("No ID".to_owned());
 
-                    let representation_description = describe_representation(rep, adaptation);
+                    let source_representation = source_adaptation_set.and_then(|adaptation_set| {
+                        adaptation_set.child(
+                            "Representation",
+                            &representation_id,
+                            representation_index,
+                        )
+                    });
+
+                    // Most specific first, the way inherited segment information is resolved
+                    let source_levels =
+                        [source_representation, source_adaptation_set, source_period];
+
+                    let representation_location = adaptation_set_location
+                        .representation(&representation_id, representation_index);
+
+                    let mut representation_warnings: Vec<ParseWarning> = vec![];
+
+                    if rep.id.is_none() {
+                        representation_warnings.push(ParseWarning::RepresentationWithoutId);
+                    }
+
+                    let representation_description =
+                        match describe_representation(rep, adaptation, content_type) {
+                            Ok(description) => description,
+                            // The description is only shown, segments are expanded without it
+                            Err(err) => {
+                                representation_warnings.push(
+                                    ParseWarning::IncompleteRepresentationDescription(Box::new(
+                                        err,
+                                    )),
+                                );
+
+                                summarise_representation(rep, adaptation).join(" ")
+                            }
+                        };
 
                     debug!(
                         "\n  Representation {}: {}",
                         representation_id, representation_description
                     );
 
-                    let segments = parse_segment_template(
-                        &rep.SegmentTemplate,
-                        &adaptation.SegmentTemplate,
-                        &p.SegmentTemplate,
-                        period_start_ms,
-                    );
+                    let levels = [
+                        (
+                            rep.SegmentList.is_some(),
+                            rep.SegmentTemplate.is_some(),
+                            rep.SegmentBase.is_some(),
+                        ),
+                        (
+                            adaptation.SegmentList.is_some(),
+                            adaptation.SegmentTemplate.is_some(),
+                            adaptation_segment_base.is_some(),
+                        ),
+                        (
+                            period_segment_list.is_some(),
+                            p.SegmentTemplate.is_some(),
+                            period_segment_base.is_some(),
+                        ),
+                    ];
+
+                    let segments = match match addressing(levels) {
+                        Addressing::List => parse_segment_list(
+                            [
+                                &rep.SegmentList,
+                                &adaptation.SegmentList,
+                                &period_segment_list,
+                            ],
+                            source_attribute(
+                                &source_levels,
+                                "SegmentList",
+                                "presentationTimeOffset",
+                            ),
+                            period_start,
+                            period_end,
+                            &mut representation_warnings,
+                        ),
+                        Addressing::Base => parse_segment_base(
+                            [
+                                &rep.SegmentBase,
+                                &adaptation_segment_base,
+                                &period_segment_base,
+                            ],
+                            &rep.BaseURL,
+                            period_start,
+                            period_end,
+                            media_dir,
+                            &mut representation_warnings,
+                        ),
+                        Addressing::Template => parse_segment_template(
+                            &rep.SegmentTemplate,
+                            &adaptation.SegmentTemplate,
+                            &p.SegmentTemplate,
+                            period_start,
+                            period_end,
+                            live_window,
+                            &mut representation_warnings,
+                        ),
+                    } {
+                        Ok(segments) => segments,
+                        Err(err) => {
+                            skip_or_fail(err.at(&representation_location), mode, &mut warnings)?;
+                            continue;
+                        }
+                    };
 
-                    representations.push(ExpandedRepresentation { segments });
+                    collect_warnings(
+                        representation_warnings,
+                        &representation_location,
+                        mode,
+                        &mut warnings,
+                    )?;
+
+                    representations.push(ExpandedRepresentation {
+                        id: representation_id,
+                        source_index: representation_index,
+                        description: representation_description,
+                        summary: summarise_representation(rep, adaptation),
+                        bandwidth: rep.bandwidth,
+                        segments,
+                    });
+                }
+
+                // Only reachable in lenient mode, every Representation was skipped
+                if representations.is_empty() {
+                    continue;
                 }
 
                 let adaptation_set = ExpandedAdaptationSet {
+                    id: adaptation_set_id,
+                    source_index: adaptation_index,
+                    content_type,
+                    lang: adaptation.lang.clone(),
+                    roles: adaptation
+                        .Role
+                        .iter()
+                        .filter_map(|role| role.value.clone())
+                        .collect(),
                     representations,
-                    content_type: content_type.to_owned(),
                 };
 
                 adaptation_sets.push(adaptation_set);
             }
+            if adaptation_sets.is_empty() {
+                continue;
+            }
+
             let period = ExpandedPeriod {
-                period_start_ms,
-                period_duration_ms,
+                period_start,
+                period_duration,
                 adaptation_sets,
                 id: period_id,
+                source_index: index,
             };
 
-            previous_period_end_ms = period.end_ms();
+            previous_period_end = Some(period.end());
 
             _periods.push(period);
         }
 
-        ExpandedMpd { periods: _periods }
+        if _periods.is_empty() {
+            // In lenient mode everything may have been skipped, report the first reason why
+            let skipped = warnings.into_iter().find_map(|warning| match warning {
+                ParseWarning::Skipped(err) => Some(*err),
+                _ => None,
+            });
+
+            return Err(skipped.unwrap_or(ParseError::MpdWithoutPeriods));
+        }
+
+        Ok(ExpandedMpd {
+            periods: _periods,
+            warnings,
+            live,
+        })
+    }
+}
+
+/// How the segments of a Representation are addressed
+enum Addressing {
+    List,
+    Template,
+    Base,
+}
+
+/// Decided by the most specific of the Representation, AdaptationSet and Period `levels` with
+/// segment information, given as whether each has a SegmentList, SegmentTemplate and SegmentBase.
+/// A SegmentList takes precedence over a SegmentTemplate on the same level, and a single file is
+/// assumed when there is none.
+fn addressing(levels: [(bool, bool, bool); 3]) -> Addressing {
+    levels
+        .into_iter()
+        .find_map(|level| match level {
+            (true, _, _) => Some(Addressing::List),
+            (_, true, _) => Some(Addressing::Template),
+            (_, _, true) => Some(Addressing::Base),
+            _ => None,
+        })
+        .unwrap_or(Addressing::Base)
+}
+
+/// Locates and records the warnings raised while expanding one part of the manifest. In strict
+/// mode the first of them is returned as an error instead.
+fn collect_warnings(
+    raised: Vec<ParseWarning>,
+    location: &ManifestLocation,
+    mode: ParseMode,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<(), ParseError> {
+    for warning in raised {
+        let warning = warning.at(location);
+
+        debug!("{}", warning.describe().trim());
+
+        if mode == ParseMode::Strict {
+            return Err(ParseError::StrictWarning(Box::new(warning)));
+        }
+
+        warnings.push(warning);
+    }
+
+    Ok(())
+}
+
+/// In lenient mode records `err` as a warning so the caller can skip the failing part of the
+/// manifest, otherwise returns it.
+fn skip_or_fail(
+    err: ParseError,
+    mode: ParseMode,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<(), ParseError> {
+    match mode {
+        ParseMode::Lenient => {
+            debug!("{}", err.describe().trim());
+
+            warnings.push(ParseWarning::Skipped(Box::new(err)));
+
+            Ok(())
+        }
+        _ => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{expanded::ExpandedSegments, util::fixture::manifest};
+
+    const TEMPLATE: &str =
+        r#"<SegmentTemplate timescale="1000" duration="2000" media="$Number$.m4s"/>"#;
+
+    fn first_segments(mpd: &ExpandedMpd) -> &ExpandedSegments {
+        &mpd.periods[0].adaptation_sets[0].representations[0].segments
+    }
+
+    #[test]
+    fn segment_list_presentation_time_offset() {
+        let mpd = manifest()
+            .representation_child(
+                r#"<SegmentList timescale="1000" presentationTimeOffset="10000">
+          <SegmentTimeline><S t="10000" d="2000" r="2"/></SegmentTimeline>
+          <SegmentURL media="1.m4s"/><SegmentURL media="2.m4s"/><SegmentURL media="3.m4s"/>
+        </SegmentList>"#,
+            )
+            .expand(ParseMode::Default)
+            .unwrap();
+
+        let segment_timeline = first_segments(&mpd).segment_timeline();
+
+        assert_eq!(segment_timeline.start().as_ms(), 0);
+        assert_eq!(segment_timeline.end().as_ms(), 6000);
+        assert_eq!(segment_timeline.segments[0].presentation_time_offset, 10000);
+    }
+
+    #[test]
+    fn segment_list_presentation_time_offset_inherited() {
+        let mpd = manifest()
+            .adaptation_set_child(r#"<SegmentList presentationTimeOffset="4000"/>"#)
+            .representation_child(
+                r#"<SegmentList timescale="1000">
+          <SegmentTimeline><S t="4000" d="2000" r="2"/></SegmentTimeline>
+          <SegmentURL media="1.m4s"/><SegmentURL media="2.m4s"/><SegmentURL media="3.m4s"/>
+        </SegmentList>"#,
+            )
+            .expand(ParseMode::Default)
+            .unwrap();
+
+        assert_eq!(first_segments(&mpd).segment_timeline().start().as_ms(), 0);
+    }
+
+    #[test]
+    fn segment_base_inherited_from_adaptation_set() {
+        let mpd = manifest()
+            .adaptation_set_child(r#"<SegmentBase timescale="1000" presentationDuration="4000"/>"#)
+            .representation_child("<BaseURL>v1.mp4</BaseURL>")
+            .expand(ParseMode::Default)
+            .unwrap();
+
+        assert!(matches!(
+            first_segments(&mpd),
+            ExpandedSegments::SegmentBase { indexed: false, .. }
+        ));
+        assert_eq!(first_segments(&mpd).segment_timeline().end().as_ms(), 4000);
+    }
+
+    #[test]
+    fn segment_base_inherited_from_period() {
+        let manifest = manifest()
+            .period_child(r#"<SegmentBase timescale="1000" presentationDuration="5000"/>"#)
+            .representation_child("<BaseURL>v1.mp4</BaseURL>");
+
+        // Inheriting is reported, so strict mode fails on it
+        assert!(manifest.expand(ParseMode::Strict).is_err());
+
+        let mpd = manifest.expand(ParseMode::Default).unwrap();
+
+        assert_eq!(first_segments(&mpd).segment_timeline().end().as_ms(), 5000);
+    }
+
+    #[test]
+    fn segment_timeline_past_the_live_edge() {
+        // The live edge is 60s in, the only S starts at 120s
+        let manifest = manifest()
+            .dynamic("2024-01-01T00:00:00Z", "2024-01-01T00:01:00Z")
+            .adaptation_set_child(
+                r#"<SegmentTemplate timescale="1000" media="$Time$.m4s">
+        <SegmentTimeline><S t="120000" d="2000" r="-1"/></SegmentTimeline>
+      </SegmentTemplate>"#,
+            );
+
+        for mode in [ParseMode::Default, ParseMode::Lenient] {
+            let err = manifest.expand(mode).unwrap_err();
+
+            assert!(
+                err.describe().contains("SegmentTimelineWithoutSegments"),
+                "{}",
+                err.describe()
+            );
+        }
+    }
+
+    #[test]
+    fn segment_timeline_end_with_backward_jump() {
+        let mpd = manifest()
+            .representation_child(
+                r#"<SegmentTemplate timescale="1000" media="$Time$.m4s">
+          <SegmentTimeline><S t="2000" d="2000" r="1"/><S t="0" d="2000"/></SegmentTimeline>
+        </SegmentTemplate>"#,
+            )
+            .expand(ParseMode::Default)
+            .unwrap();
+
+        let segment_timeline = first_segments(&mpd).segment_timeline();
+
+        // The last run ends at 2s, before the first one does
+        assert_eq!(segment_timeline.start().as_ms(), 0);
+        assert_eq!(segment_timeline.end().as_ms(), 6000);
+    }
+
+    #[test]
+    fn segment_list_inherited_from_period() {
+        let mpd = manifest()
+            .period_child(
+                r#"<SegmentList timescale="1000" duration="2000">
+      <SegmentURL media="1.m4s"/><SegmentURL media="2.m4s"/><SegmentURL media="3.m4s"/>
+    </SegmentList>"#,
+            )
+            .expand(ParseMode::Default)
+            .unwrap();
+
+        let ExpandedSegments::SegmentList { media, .. } = first_segments(&mpd) else {
+            panic!("Expecting a SegmentList, got {:?}", first_segments(&mpd));
+        };
+
+        assert_eq!(media, &["1.m4s", "2.m4s", "3.m4s"]);
+        assert_eq!(first_segments(&mpd).segment_timeline().end().as_ms(), 6000);
+
+        let warnings: Vec<String> = mpd
+            .warnings
+            .iter()
+            .map(|warning| warning.describe())
+            .collect();
+
+        assert!(warnings
+            .iter()
+            .any(|warning| warning.contains("SegmentListInheritedFromPeriod")));
+        assert!(!warnings.iter().any(|warning| warning
+            .contains("SegmentListInheritedFromAdaptationSet")
+            || warning.contains("DefaultedTimescale")));
+    }
+
+    #[test]
+    fn representation_described_from_what_is_known() {
+        let mpd = manifest()
+            .without_adaptation_set_attribute("frameRate")
+            .adaptation_set_child(TEMPLATE)
+            .expand(ParseMode::Default)
+            .unwrap();
+
+        let representation = &mpd.periods[0].adaptation_sets[0].representations[0];
+
+        assert_eq!(
+            representation.segments.segment_timeline().segment_count(),
+            3
+        );
+        assert_eq!(representation.description, "1280x720 1Mbps avc1.64001f");
+        assert!(mpd.warnings.iter().any(|warning| {
+            let warning = warning.describe();
+
+            warning.contains("IncompleteRepresentationDescription")
+                && warning.contains("CannotInferRepresentationFrameRate")
+        }));
+    }
+
+    #[test]
+    fn period_start_inferred_from_previous_period() {
+        let mpd = manifest()
+            .without_period_attribute("start")
+            .adaptation_set_child(TEMPLATE)
+            .mpd_attribute("mediaPresentationDuration", "PT12S")
+            .period_attribute("duration", "PT6S")
+            .period("p1", "")
+            .adaptation_set_child(TEMPLATE)
+            .expand(ParseMode::Default)
+            .unwrap();
+
+        assert_eq!(mpd.periods[1].period_start.as_ms(), 6000);
+
+        let inferred: Vec<String> = mpd
+            .warnings
+            .iter()
+            .map(|warning| warning.describe())
+            .filter(|warning| warning.contains("PeriodStartInferredFromPreviousPeriod"))
+            .collect();
+
+        // Only the second Period has a previous Period to start from
+        let [warning] = &inferred[..] else {
+            panic!("Expecting a single warning, got {:?}", inferred);
+        };
+
+        assert!(warning.contains("Period p1"));
     }
 }
@@ -0,0 +1,51 @@
+use serde::{Serialize, Serializer};
+
+use super::{drift::BoundaryDrift, Expanded, ExpandedMpd, MediaTime};
+
+/// Serialises an element along with the start and end of the segments it contains
+#[derive(Serialize)]
+struct WithExtent<'a, T: Serialize> {
+    start: MediaTime,
+    end: MediaTime,
+    #[serde(flatten)]
+    element: &'a T,
+}
+
+impl<'a, T: Expanded + Serialize> WithExtent<'a, T> {
+    fn new(element: &'a T) -> Self {
+        WithExtent {
+            start: element.start(),
+            end: element.end(),
+            element,
+        }
+    }
+}
+
+/// Used as `serialize_with` on the children of an element, so every level of the model carries
+/// its computed start and end
+pub fn with_extents<S, T>(elements: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Expanded + Serialize,
+{
+    serializer.collect_seq(elements.iter().map(WithExtent::new))
+}
+
+/// The model with what is derived from it across Periods
+#[derive(Serialize)]
+struct Export<'a> {
+    #[serde(flatten)]
+    manifest: WithExtent<'a, ExpandedMpd>,
+    boundary_drift: Vec<BoundaryDrift>,
+}
+
+impl ExpandedMpd {
+    /// Dumps the whole expanded model, down to the individual runs of segments, and the drift at
+    /// every Period boundary flagged above `drift_threshold`
+    pub fn to_json(&self, drift_threshold: MediaTime) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&Export {
+            manifest: WithExtent::new(self),
+            boundary_drift: self.boundary_drift(drift_threshold),
+        })
+    }
+}
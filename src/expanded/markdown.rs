@@ -0,0 +1,226 @@
+use super::{
+    Discontinuity, Expanded, ExpandedAdaptationSet, ExpandedMpd, ExpandedPeriod,
+    ExpandedRepresentation, MediaTime,
+};
+use crate::{push_fmt, push_line};
+
+impl ExpandedMpd {
+    /// Describes the manifest as markdown: a section per Period with its timing and gaps, then a
+    /// table per AdaptationSet summarising the segments of every Representation.
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+
+        push_line!(md, "# Manifest\n");
+        push_line!(md, "| | |\n|---|---|");
+        push_line!(
+            md,
+            "| Type | {} |",
+            if self.live.is_some() {
+                "dynamic"
+            } else {
+                "static"
+            }
+        );
+        push_line!(md, "| Periods | {} |", self.periods.len());
+        push_line!(
+            md,
+            "| Segments | {} to {} ({}) |",
+            self.start(),
+            self.end(),
+            self.end().saturating_sub(self.start())
+        );
+
+        if let Some(live) = &self.live {
+            push_line!(
+                md,
+                "| availabilityStartTime | {} |",
+                live.availability_start_time.to_rfc3339()
+            );
+            push_line!(md, "| publishTime | {} |", live.publish_time.to_rfc3339());
+            push_line!(
+                md,
+                "| Availability window | {} to {} |",
+                live.window_start,
+                live.live_edge
+            );
+            push_line!(md, "| Presentation edge | {} |", live.presentation_edge());
+        }
+
+        if !self.warnings.is_empty() {
+            push_line!(md, "| Warnings | {} |", self.warnings.len());
+        }
+
+        let mut previous_period: Option<&ExpandedPeriod> = None;
+
+        for period in self.periods.iter() {
+            write_period(&mut md, period, previous_period);
+
+            previous_period = Some(period);
+        }
+
+        if !self.warnings.is_empty() {
+            push_line!(md, "\n## Warnings\n");
+
+            for warning in self.warnings.iter() {
+                push_line!(md, "- {}", warning.describe().trim());
+            }
+        }
+
+        md
+    }
+}
+
+fn write_period(md: &mut String, period: &ExpandedPeriod, previous: Option<&ExpandedPeriod>) {
+    push_line!(md, "\n## Period {}\n", escape(&period.id));
+    push_line!(md, "| | |\n|---|---|");
+    push_line!(md, "| Start | {} |", period.period_start);
+    push_line!(
+        md,
+        "| Duration | {} |",
+        period
+            .period_duration
+            .map_or("-".to_owned(), |duration| duration.to_string())
+    );
+    push_line!(
+        md,
+        "| Segments | {} to {} ({}) |",
+        period.start(),
+        period.end(),
+        period.end().saturating_sub(period.start())
+    );
+
+    // Compared to where the previous Period's segments end, or Period@start for the first one
+    let (reference, reference_name) = match previous {
+        Some(previous) => (previous.end(), "previous Period"),
+        None => (period.period_start, "Period@start"),
+    };
+
+    push_line!(
+        md,
+        "| Gap to {} | {} |",
+        reference_name,
+        format_offset(reference, period.start())
+    );
+
+    if let Some(duration) = period.period_duration {
+        push_line!(
+            md,
+            "| Segments end vs Period@duration | {} |",
+            format_offset(period.period_start + duration, period.end())
+        );
+    }
+
+    for adaptation_set in period.adaptation_sets.iter() {
+        write_adaptation_set(md, adaptation_set);
+    }
+}
+
+fn write_adaptation_set(md: &mut String, adaptation_set: &ExpandedAdaptationSet) {
+    push_fmt!(
+        md,
+        "\n### AdaptationSet {} ({}",
+        escape(&adaptation_set.id),
+        adaptation_set.content_type.as_str()
+    );
+
+    if let Some(lang) = &adaptation_set.lang {
+        push_fmt!(md, ", {}", escape(lang));
+    }
+
+    for role in adaptation_set.roles.iter() {
+        push_fmt!(md, ", {}", escape(role));
+    }
+
+    push_line!(md, ")\n");
+
+    push_line!(
+        md,
+        "| Representation | Description | Addressing | Segments | Durations | Start | End | Discontinuities |"
+    );
+    push_line!(md, "|---|---|---|---|---|---|---|---|");
+
+    for representation in adaptation_set.representations.iter() {
+        write_representation(md, representation);
+    }
+
+    let discontinuities: Vec<(&ExpandedRepresentation, Discontinuity)> = adaptation_set
+        .representations
+        .iter()
+        .flat_map(|representation| {
+            representation
+                .segments
+                .segment_timeline()
+                .discontinuities()
+                .into_iter()
+                .map(move |discontinuity| (representation, discontinuity))
+        })
+        .collect();
+
+    if !discontinuities.is_empty() {
+        push_line!(md);
+    }
+
+    for (representation, discontinuity) in discontinuities {
+        let kind = match discontinuity {
+            Discontinuity::Gap { .. } => "gap",
+            Discontinuity::Overlap { .. } => "overlap",
+        };
+
+        push_line!(
+            md,
+            "- Representation {}: {} {} at {}",
+            escape(&representation.id),
+            discontinuity.duration(),
+            kind,
+            discontinuity.start()
+        );
+    }
+}
+
+fn write_representation(md: &mut String, representation: &ExpandedRepresentation) {
+    let segment_timeline = representation.segments.segment_timeline();
+
+    // Distinct segment durations in the order they first appear
+    let mut durations: Vec<MediaTime> = vec![];
+
+    for segment in segment_timeline.segments.iter() {
+        if !durations.contains(&segment.segment_duration) {
+            durations.push(segment.segment_duration);
+        }
+    }
+
+    let durations = durations
+        .into_iter()
+        .map(|time| time.to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    push_line!(
+        md,
+        "| {} | {} | {} | {} | {} | {} | {} | {} |",
+        escape(&representation.id),
+        escape(&representation.description),
+        representation.segments.kind(),
+        segment_timeline.segment_count(),
+        durations,
+        representation.start(),
+        representation.end(),
+        segment_timeline.discontinuities().len()
+    );
+}
+
+/// How far `actual` is from `expected`, e.g. +0.200s late or -0.200s early
+fn format_offset(expected: MediaTime, actual: MediaTime) -> String {
+    if actual > expected {
+        format!("+{}", actual.saturating_sub(expected))
+    } else if actual < expected {
+        format!("-{}", expected.saturating_sub(actual))
+    } else {
+        "none".to_owned()
+    }
+}
+
+/// Keeps manifest values from breaking table cells
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
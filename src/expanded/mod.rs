@@ -1,153 +1,440 @@
+mod drift;
 mod expand;
-mod png;
+mod json;
+mod markdown;
+mod render;
+mod time;
+
+pub use drift::DEFAULT_DRIFT_THRESHOLD_MS;
+pub use render::{RenderOptions, TimeLabels, DEFAULT_MAX_HEIGHT};
+pub use time::{MediaOffset, MediaTime};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::util::{error::ParseWarning, parse::resolve_media_template};
 
 pub trait Expanded {
-    fn start_ms(&self) -> u64;
-    fn end_ms(&self) -> u64;
+    fn start(&self) -> MediaTime;
+    fn end(&self) -> MediaTime;
     // fn duration_seconds(&self) -> f64;
+
+    fn start_ms(&self) -> u64 {
+        self.start().as_ms()
+    }
+    fn end_ms(&self) -> u64 {
+        self.end().as_ms()
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 #[allow(dead_code)]
 pub struct ExpandedMpd {
+    #[serde(serialize_with = "json::with_extents")]
     pub periods: Vec<ExpandedPeriod>,
+    /// Fallbacks taken while expanding, and in lenient mode the parts that were skipped
+    pub warnings: Vec<ParseWarning>,
+    /// Only present for dynamic manifests
+    pub live: Option<ExpandedLive>,
+}
+
+/// The timing of a dynamic manifest as seen at MPD@publishTime (or now when not present).
+/// Presentation times are relative to MPD@availabilityStartTime.
+#[derive(Debug, Serialize)]
+#[allow(dead_code)]
+pub struct ExpandedLive {
+    pub availability_start_time: DateTime<Utc>,
+    pub publish_time: DateTime<Utc>,
+    pub time_shift_buffer_depth: Option<MediaTime>,
+    pub suggested_presentation_delay: Option<MediaTime>,
+    /// Earliest presentation time still available, the live edge minus timeShiftBufferDepth
+    pub window_start: MediaTime,
+    /// Presentation time of the newest segment that can have been published
+    pub live_edge: MediaTime,
+}
+
+impl ExpandedLive {
+    /// The wall-clock time a presentation time maps to
+    pub fn to_utc(&self, time: MediaTime) -> DateTime<Utc> {
+        self.availability_start_time
+            + Duration::nanoseconds(time.to_timescale(1_000_000_000) as i64)
+    }
+
+    /// Where a player following @suggestedPresentationDelay would be playing
+    pub fn presentation_edge(&self) -> MediaTime {
+        match self.suggested_presentation_delay {
+            Some(delay) => self.live_edge.saturating_sub(delay),
+            None => self.live_edge,
+        }
+    }
+}
+
+impl ExpandedMpd {
+    /// Every Period starting before the segments of the previous Period end, by the index of the
+    /// later Period
+    pub fn period_overlaps(&self) -> Vec<(usize, Discontinuity)> {
+        self.periods
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| pair[1].period_start < pair[0].end())
+            .map(|(index, pair)| {
+                (
+                    index + 1,
+                    Discontinuity::Overlap {
+                        start: pair[1].period_start,
+                        end: pair[0].end(),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// How the expander treats fallbacks and errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Errors abort the expansion, warnings are only collected
+    #[default]
+    Default,
+    /// Parts of the manifest that cannot be expanded are skipped and reported as warnings
+    Lenient,
+    /// The first warning aborts the expansion
+    Strict,
 }
 
 impl Expanded for ExpandedMpd {
-    fn start_ms(&self) -> u64 {
+    fn start(&self) -> MediaTime {
         self.periods
             .first()
             .expect("Manifest with no periods")
-            .start_ms()
+            .start()
     }
-    fn end_ms(&self) -> u64 {
-        self.periods
-            .last()
-            .expect("Manifest with no periods")
-            .end_ms()
+    fn end(&self) -> MediaTime {
+        self.periods.last().expect("Manifest with no periods").end()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 #[allow(dead_code)]
 pub struct ExpandedPeriod {
+    #[serde(serialize_with = "json::with_extents")]
     pub adaptation_sets: Vec<ExpandedAdaptationSet>,
-    pub period_start_ms: u64,
-    pub period_duration_ms: Option<u64>,
+    pub period_start: MediaTime,
+    pub period_duration: Option<MediaTime>,
     pub id: String,
+    /// Position among the elements of the same name in the manifest. Unlike the position in the
+    /// model it counts elements skipped in lenient mode.
+    #[serde(skip)]
+    pub source_index: usize,
 }
 
+/// From the earliest start to the latest end of every track, tracks rarely line up exactly
 impl Expanded for ExpandedPeriod {
-    fn start_ms(&self) -> u64 {
-        let segments_start_ms = self
-            .adaptation_sets
-            .first()
+    fn start(&self) -> MediaTime {
+        self.adaptation_sets
+            .iter()
+            .map(Expanded::start)
+            .min()
             .expect("No adaptation sets")
-            .start_ms();
-
-        segments_start_ms
     }
 
-    fn end_ms(&self) -> u64 {
-        let segments_end_ms = self
-            .adaptation_sets
-            .first()
+    fn end(&self) -> MediaTime {
+        self.adaptation_sets
+            .iter()
+            .map(Expanded::end)
+            .max()
             .expect("No adaptation sets")
-            .end_ms();
+    }
+}
+
+impl ExpandedPeriod {
+    /// Where the segments end before Period@start + Period@duration, a gap, or run past it, an
+    /// overlap. None without a Period@duration or when they line up.
+    pub fn duration_mismatch(&self) -> Option<Discontinuity> {
+        let duration_end = self.period_start + self.period_duration?;
+        let segments_end = self.end();
 
-        segments_end_ms
+        match segments_end.cmp(&duration_end) {
+            std::cmp::Ordering::Less => Some(Discontinuity::Gap {
+                start: segments_end,
+                end: duration_end,
+            }),
+            std::cmp::Ordering::Greater => Some(Discontinuity::Overlap {
+                start: duration_end,
+                end: segments_end,
+            }),
+            std::cmp::Ordering::Equal => None,
+        }
     }
 }
 
-#[derive(Debug)]
+/// The AdaptationSet@contentType values dmpd knows how to describe and draw
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentType {
+    Audio,
+    Video,
+    Text,        // subtitles and captions, e.g. WebVTT or TTML
+    Image,       // thumbnail tiles
+    Application, // timed metadata, e.g. application/mp4 event tracks
+}
+
+impl ContentType {
+    pub fn from_str(content_type: &str) -> Option<ContentType> {
+        match content_type {
+            "audio" => Some(ContentType::Audio),
+            "video" => Some(ContentType::Video),
+            "text" => Some(ContentType::Text),
+            "image" => Some(ContentType::Image),
+            "application" => Some(ContentType::Application),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentType::Audio => "audio",
+            ContentType::Video => "video",
+            ContentType::Text => "text",
+            ContentType::Image => "image",
+            ContentType::Application => "application",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
 #[allow(dead_code)]
 pub struct ExpandedAdaptationSet {
-    pub content_type: String, // audio or video
+    pub id: String,
+    /// Position among the elements of the same name in the manifest. Unlike the position in the
+    /// model it counts elements skipped in lenient mode.
+    #[serde(skip)]
+    pub source_index: usize,
+    pub content_type: ContentType,
+    pub lang: Option<String>,
+    /// Role@value of every Role, e.g. main or commentary
+    pub roles: Vec<String>,
+    #[serde(serialize_with = "json::with_extents")]
     pub representations: Vec<ExpandedRepresentation>,
 }
 
 impl Expanded for ExpandedAdaptationSet {
-    fn start_ms(&self) -> u64 {
+    fn start(&self) -> MediaTime {
         self.representations
-            .first()
+            .iter()
+            .map(Expanded::start)
+            .min()
             .expect("AdaptationSet with no representations")
-            .start_ms()
     }
-    fn end_ms(&self) -> u64 {
+    fn end(&self) -> MediaTime {
         self.representations
-            .last()
+            .iter()
+            .map(Expanded::end)
+            .max()
             .expect("AdaptationSet with no representations")
-            .end_ms()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 #[allow(dead_code)]
 pub struct ExpandedRepresentation {
+    pub id: String,
+    /// Position among the elements of the same name in the manifest. Unlike the position in the
+    /// model it counts elements skipped in lenient mode.
+    #[serde(skip)]
+    pub source_index: usize,
+    /// The output of `describe_representation`, e.g. codecs, resolution and bandwidth
+    pub description: String,
+    /// The output of `summarise_representation`, a few short lines for narrow column headers
+    pub summary: Vec<String>,
+    pub bandwidth: Option<u64>,
     pub segments: ExpandedSegments,
 }
 
+impl ExpandedRepresentation {
+    /// The $Number$ of the segment at `index`, counting from the first expanded segment
+    pub fn segment_number(&self, index: u64) -> Option<u64> {
+        match &self.segments {
+            ExpandedSegments::SegmentTemplate { start_number, .. } => Some(start_number + index),
+            _ => None,
+        }
+    }
+
+    /// The media URL of the segment at `index`, starting at `time` in the timescale of the
+    /// manifest. Relative URLs are left unresolved.
+    pub fn segment_url(&self, index: u64, time: u64) -> Option<String> {
+        match &self.segments {
+            ExpandedSegments::SegmentTemplate {
+                media,
+                start_number,
+                ..
+            } => Some(resolve_media_template(
+                media,
+                &self.id,
+                start_number + index,
+                time,
+                self.bandwidth,
+            )),
+            ExpandedSegments::SegmentList { media, .. } => media.get(index as usize).cloned(),
+            ExpandedSegments::SegmentBase { base_url, .. } => base_url.clone(),
+        }
+    }
+}
+
 impl Expanded for ExpandedRepresentation {
-    fn start_ms(&self) -> u64 {
-        self.segments.start_ms()
+    fn start(&self) -> MediaTime {
+        self.segments.start()
     }
-    fn end_ms(&self) -> u64 {
-        self.segments.end_ms()
+    fn end(&self) -> MediaTime {
+        self.segments.end()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(tag = "addressing")]
 #[allow(dead_code)]
+#[allow(clippy::enum_variant_names)] // named after the DASH elements
 pub enum ExpandedSegments {
     SegmentTemplate {
         segment_timeline: ExpandedSegmentTimeline,
+        media: String,
+        start_number: u64, // $Number$ of the first expanded segment
+    },
+    SegmentList {
+        segment_timeline: ExpandedSegmentTimeline,
+        media: Vec<String>, // SegmentURL@media, in order
+    },
+    SegmentBase {
+        segment_timeline: ExpandedSegmentTimeline,
+        indexed: bool, // subsegments were read from the sidx
+        base_url: Option<String>,
     },
-    SegmentList, // TODO
 }
 
-impl Expanded for ExpandedSegments {
-    fn start_ms(&self) -> u64 {
-        match &self {
-            ExpandedSegments::SegmentTemplate { segment_timeline } => segment_timeline.start_ms(),
-            ExpandedSegments::SegmentList => 0u64,
+impl ExpandedSegments {
+    /// The addressing scheme the segments were expanded from
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ExpandedSegments::SegmentTemplate { .. } => "SegmentTemplate",
+            ExpandedSegments::SegmentList { .. } => "SegmentList",
+            ExpandedSegments::SegmentBase { .. } => "SegmentBase",
         }
     }
-    fn end_ms(&self) -> u64 {
-        match &self {
-            ExpandedSegments::SegmentTemplate { segment_timeline } => segment_timeline.end_ms(),
-            ExpandedSegments::SegmentList => 0u64,
+
+    pub fn segment_timeline(&self) -> &ExpandedSegmentTimeline {
+        match self {
+            ExpandedSegments::SegmentTemplate {
+                segment_timeline, ..
+            }
+            | ExpandedSegments::SegmentList {
+                segment_timeline, ..
+            }
+            | ExpandedSegments::SegmentBase {
+                segment_timeline, ..
+            } => segment_timeline,
         }
     }
 }
 
-#[derive(Debug)]
+impl Expanded for ExpandedSegments {
+    fn start(&self) -> MediaTime {
+        self.segment_timeline().start()
+    }
+    fn end(&self) -> MediaTime {
+        self.segment_timeline().end()
+    }
+}
+
+#[derive(Debug, Serialize)]
 #[allow(dead_code)]
 pub struct ExpandedSegmentTimeline {
     pub segments: Vec<ExpandedSegmentTimelineSegment>,
 }
 
+/// Where two consecutive spans of time do not line up, e.g. an S@t jumping forward or backward
+/// or a Period starting before the segments of the previous one end
+#[derive(Debug, Clone, Copy)]
+pub enum Discontinuity {
+    /// Nothing is addressed between the end of one span and the start of the next
+    Gap { start: MediaTime, end: MediaTime },
+    /// The next span starts before the previous one ends
+    Overlap { start: MediaTime, end: MediaTime },
+}
+
+impl Discontinuity {
+    pub fn start(&self) -> MediaTime {
+        match self {
+            Discontinuity::Gap { start, .. } | Discontinuity::Overlap { start, .. } => *start,
+        }
+    }
+
+    pub fn end(&self) -> MediaTime {
+        match self {
+            Discontinuity::Gap { end, .. } | Discontinuity::Overlap { end, .. } => *end,
+        }
+    }
+
+    pub fn duration(&self) -> MediaTime {
+        match self {
+            Discontinuity::Gap { start, end } | Discontinuity::Overlap { start, end } => {
+                end.saturating_sub(*start)
+            }
+        }
+    }
+}
+
+impl ExpandedSegmentTimeline {
+    pub fn segment_count(&self) -> u64 {
+        self.segments.iter().map(|s| s.segment_count).sum()
+    }
+
+    pub fn discontinuities(&self) -> Vec<Discontinuity> {
+        self.segments
+            .windows(2)
+            .filter_map(|pair| {
+                let (previous, next) = (&pair[0], &pair[1]);
+
+                match next.start.cmp(&previous.end) {
+                    std::cmp::Ordering::Greater => Some(Discontinuity::Gap {
+                        start: previous.end,
+                        end: next.start,
+                    }),
+                    std::cmp::Ordering::Less => Some(Discontinuity::Overlap {
+                        start: next.start,
+                        end: previous.end,
+                    }),
+                    std::cmp::Ordering::Equal => None,
+                }
+            })
+            .collect()
+    }
+}
+
+// Runs are not necessarily in order, an S@t may jump backward
 impl Expanded for ExpandedSegmentTimeline {
-    fn start_ms(&self) -> u64 {
+    fn start(&self) -> MediaTime {
         self.segments
-            .first()
+            .iter()
+            .map(|s| s.start)
+            .min()
             .expect("SegmentTimeline with no segments")
-            .start_ms
     }
-    fn end_ms(&self) -> u64 {
+    fn end(&self) -> MediaTime {
         self.segments
-            .last()
+            .iter()
+            .map(|s| s.end)
+            .max()
             .expect("SegmentTimeline with no segments")
-            .end_ms
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 #[allow(dead_code)]
 pub struct ExpandedSegmentTimelineSegment {
-    pub start_ms: u64,
-    pub end_ms: u64,
-    pub duration_ms: u64,
-    pub segment_duration_ms: u64,
+    pub start: MediaTime,
+    pub end: MediaTime,
+    pub duration: MediaTime,
+    pub segment_duration: MediaTime,
     pub segment_count: u64,
     pub presentation_time_offset: u64,
 }
@@ -0,0 +1,77 @@
+/// Define a structure to hold a queue of draw operations
+pub struct DrawQueue {
+    queue: Vec<DrawTask>,
+}
+
+pub enum DrawTask {
+    FilledRect {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        rgba: (u8, u8, u8, u8),
+        // Only vector output can show it, e.g. as an SVG <title>
+        tooltip: Option<String>,
+        // The `element_key` of the model element drawn, for interactive output
+        element: Option<String>,
+    },
+    HollowRect {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        rgba: (u8, u8, u8, u8),
+    },
+    Text {
+        x: i32,
+        y: i32,
+        scale: f32,
+        rgba: (u8, u8, u8, u8),
+        text: String,
+    },
+    Line {
+        start: (f32, f32),
+        end: (f32, f32),
+        rgba: (u8, u8, u8, u8),
+    },
+}
+
+/// Holds a queue of operations to be performed. Useful for delaying some draw operations to ensure they are placed at the correct z-index.
+impl DrawQueue {
+    // Create a new, empty draw queue
+    pub fn new() -> Self {
+        Self { queue: Vec::new() }
+    }
+
+    // Add a new draw operation to the queue
+    pub fn queue(&mut self, task: DrawTask) {
+        self.queue.push(task);
+    }
+
+    // Move every queued operation by the given offset
+    pub fn translate(&mut self, dx: i32, dy: i32) {
+        for task in self.queue.iter_mut() {
+            match task {
+                DrawTask::FilledRect { x, y, .. }
+                | DrawTask::HollowRect { x, y, .. }
+                | DrawTask::Text { x, y, .. } => {
+                    *x += dx;
+                    *y += dy;
+                }
+                DrawTask::Line { start, end, .. } => {
+                    *start = (start.0 + dx as f32, start.1 + dy as f32);
+                    *end = (end.0 + dx as f32, end.1 + dy as f32);
+                }
+            }
+        }
+    }
+
+    // Move all operations of `other` to the end of this queue
+    pub fn append(&mut self, other: &mut DrawQueue) {
+        self.queue.append(&mut other.queue);
+    }
+
+    pub fn tasks(&self) -> &[DrawTask] {
+        &self.queue
+    }
+}
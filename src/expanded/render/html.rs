@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+
+use super::{element_key, svg::SvgRenderer, Layout, RenderOptions, Renderer};
+use crate::{
+    expanded::{Expanded, ExpandedMpd},
+    push_line,
+    util::{
+        source::{find_element, scan_elements, SourceElement},
+        xml::escape,
+    },
+};
+
+const STYLE: &str = include_str!("report.css");
+const SCRIPT: &str = include_str!("report.js");
+
+/// Wraps the SVG of a layout in a self-contained page: collapsible panels for every element of
+/// the model, zoom and pan along the time axis, and the manifest source with the selected
+/// element highlighted. Nothing is loaded from the network.
+pub struct HtmlRenderer<'a> {
+    pub mpd: &'a ExpandedMpd,
+    /// The manifest the model was expanded from
+    pub source: &'a str,
+    pub title: &'a str,
+}
+
+impl Renderer for HtmlRenderer<'_> {
+    type Output = String;
+
+    fn render(&self, layout: &Layout) -> Self::Output {
+        let source_elements = scan_elements(self.source);
+
+        let mut html = String::new();
+
+        push_line!(html, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>");
+        push_line!(html, "<meta charset=\"utf-8\">");
+        push_line!(html, "<title>{}</title>", escape(self.title));
+        push_line!(html, "<style>\n{}</style>\n</head>\n<body>", STYLE);
+
+        push_line!(
+            html,
+            r#"<header><h1>{}</h1><span>{}</span><span class="controls"><button data-zoom="in">+</button><button data-zoom="out">&minus;</button><button data-zoom="reset">Reset</button> Ctrl + scroll to zoom, drag to pan</span></header>"#,
+            escape(self.title),
+            escape(&self.summary())
+        );
+
+        push_line!(html, "<main>\n<nav id=\"elements\">");
+        self.write_panels(&mut html);
+        push_line!(html, "</nav>");
+
+        push_line!(html, "<section id=\"timeline\">");
+        html.push_str(&SvgRenderer.render(layout));
+        push_line!(html, "</section>");
+
+        push_line!(html, "<section id=\"source\"><pre>");
+
+        for (index, line) in self.source.lines().enumerate() {
+            push_line!(
+                html,
+                r#"<span class="line" id="L{}">{}</span>"#,
+                index + 1,
+                escape(line)
+            );
+        }
+
+        push_line!(html, "</pre></section>\n</main>");
+        push_line!(html, "<div id=\"tooltip\"></div>");
+
+        push_line!(
+            html,
+            "<script>\nconst SOURCE_LINES = {};\n{}</script>",
+            serde_json::to_string(&self.source_lines(&source_elements)).unwrap_or_default(),
+            SCRIPT
+        );
+        push_line!(html, "</body>\n</html>");
+
+        html
+    }
+}
+
+impl HtmlRenderer<'_> {
+    fn summary(&self) -> String {
+        format!(
+            "{}, {} Periods, {} to {}",
+            if self.mpd.live.is_some() {
+                "dynamic"
+            } else {
+                "static"
+            },
+            self.mpd.periods.len(),
+            self.mpd.start(),
+            self.mpd.end()
+        )
+    }
+
+    /// Nested Period, AdaptationSet and Representation panels, keyed like the layout
+    fn write_panels(&self, html: &mut String) {
+        for (period_index, period) in self.mpd.periods.iter().enumerate() {
+            push_line!(
+                html,
+                r#"<details open data-element="{}"><summary>Period {} <small>{} to {}</small></summary>"#,
+                element_key(&[period_index]),
+                escape(&period.id),
+                period.start(),
+                period.end()
+            );
+
+            for (adaptation_index, adaptation_set) in period.adaptation_sets.iter().enumerate() {
+                push_line!(
+                    html,
+                    r#"<details open data-element="{}"><summary>AdaptationSet {} <small>{}{}</small></summary>"#,
+                    element_key(&[period_index, adaptation_index]),
+                    escape(&adaptation_set.id),
+                    adaptation_set.content_type.as_str(),
+                    adaptation_set
+                        .lang
+                        .as_ref()
+                        .map(|lang| format!(" {}", escape(lang)))
+                        .unwrap_or_default()
+                );
+
+                for (representation_index, representation) in
+                    adaptation_set.representations.iter().enumerate()
+                {
+                    let segment_timeline = representation.segments.segment_timeline();
+
+                    push_line!(
+                        html,
+                        r#"<details data-element="{}"><summary>Representation {}</summary>"#,
+                        element_key(&[period_index, adaptation_index, representation_index]),
+                        escape(&representation.id)
+                    );
+                    push_line!(
+                        html,
+                        "<p>{}</p>\n<ul><li>{}, {} segments</li><li>{} to {}</li><li>{} discontinuities</li></ul>\n</details>",
+                        escape(&representation.description),
+                        representation.segments.kind(),
+                        segment_timeline.segment_count(),
+                        representation.start(),
+                        representation.end(),
+                        segment_timeline.discontinuities().len()
+                    );
+                }
+
+                push_line!(html, "</details>");
+            }
+
+            push_line!(html, "</details>");
+        }
+    }
+
+    /// The first and last source line of every element of the model, by `element_key`. Elements
+    /// are found by their position in the manifest, which differs from the position in the model
+    /// when elements were skipped.
+    fn source_lines(&self, source_elements: &[SourceElement]) -> BTreeMap<String, [usize; 2]> {
+        let mut lines = BTreeMap::new();
+
+        let span = |element: &SourceElement| [element.start_line, element.end_line];
+
+        for (period_index, period) in self.mpd.periods.iter().enumerate() {
+            let Some(source_period) =
+                find_element(source_elements, "Period", &period.id, period.source_index)
+            else {
+                continue;
+            };
+
+            lines.insert(element_key(&[period_index]), span(source_period));
+
+            for (adaptation_index, adaptation_set) in period.adaptation_sets.iter().enumerate() {
+                let Some(source_adaptation_set) = source_period.child(
+                    "AdaptationSet",
+                    &adaptation_set.id,
+                    adaptation_set.source_index,
+                ) else {
+                    continue;
+                };
+
+                lines.insert(
+                    element_key(&[period_index, adaptation_index]),
+                    span(source_adaptation_set),
+                );
+
+                for (representation_index, representation) in
+                    adaptation_set.representations.iter().enumerate()
+                {
+                    if let Some(source_representation) = source_adaptation_set.child(
+                        "Representation",
+                        &representation.id,
+                        representation.source_index,
+                    ) {
+                        lines.insert(
+                            element_key(&[period_index, adaptation_index, representation_index]),
+                            span(source_representation),
+                        );
+                    }
+                }
+            }
+        }
+
+        lines
+    }
+}
+
+impl ExpandedMpd {
+    /// A single interactive HTML page for the manifest `source`. Like raster output the initial
+    /// scale fits `max_height`, the page can be zoomed in from there.
+    pub fn to_html(&self, source: &str, title: &str, options: &RenderOptions) -> String {
+        let scale = options
+            .pixels_per_second
+            .unwrap_or_else(|| self.fit_pixels_per_second(options.max_height));
+
+        HtmlRenderer {
+            mpd: self,
+            source,
+            title,
+        }
+        .render(&self.layout(scale, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{expanded::ParseMode, util::fixture::manifest};
+
+    #[test]
+    fn source_lines_after_a_skipped_element() {
+        // The first AdaptationSet has no Representations and is skipped
+        let manifest = manifest()
+            .period_child(r#"<AdaptationSet contentType="video"/>"#)
+            .without_adaptation_set_attribute("id")
+            .adaptation_set_child(
+                r#"<SegmentTemplate timescale="1000" duration="2000" media="$Number$.m4s"/>"#,
+            );
+        let xml = manifest.xml();
+        let mpd = manifest.expand(ParseMode::Lenient).unwrap();
+
+        let renderer = HtmlRenderer {
+            mpd: &mpd,
+            source: &xml,
+            title: "test",
+        };
+
+        let lines = renderer.source_lines(&scan_elements(&xml));
+
+        assert_eq!(lines[&element_key(&[0, 0])], [5, 9]);
+        assert_eq!(lines[&element_key(&[0, 0, 0])], [7, 8]);
+    }
+}
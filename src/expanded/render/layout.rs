@@ -0,0 +1,1627 @@
+use crate::util::error::DrawError;
+
+use crate::debug;
+
+use ab_glyph::{point, Font, FontRef, GlyphId, PxScale, ScaleFont};
+use chrono::DateTime;
+
+use super::{
+    draw_queue::{DrawQueue, DrawTask},
+    element_key, tick_interval, Layout, PixelSpacing, RenderOptions, TimeLabels,
+};
+use crate::expanded::{
+    drift::AdaptationSetDrift, ContentType, Discontinuity, Expanded, ExpandedAdaptationSet,
+    ExpandedMpd, ExpandedPeriod, ExpandedRepresentation, ExpandedSegmentTimelineSegment, MediaTime,
+};
+
+const IMAGE_PADDING: PixelSpacing = 60;
+
+/// Room left of the Periods for the time axis and its labels
+const RULER_WIDTH: PixelSpacing = 70;
+/// Fewest pixels between two labelled ticks
+const RULER_TICK_SPACING: PixelSpacing = 60;
+const RULER_FONT_SIZE: f32 = 12.0;
+
+/// Used unless a smaller scale is needed to fit within the maximum image height
+pub const DEFAULT_PIXELS_PER_SECOND: f64 = 40.0;
+pub const DEFAULT_MAX_HEIGHT: PixelSpacing = 8000;
+/// Used when the maximum image height leaves no room for the segments at all
+const MIN_PIXELS_PER_SECOND: f64 = 0.001;
+
+/// Runs of segments shorter than this are drawn as a single labelled block
+const MIN_SEGMENT_HEIGHT: i32 = 3;
+const RUN_LABEL_FONT_SIZE: f32 = 11.0;
+
+const PERIOD_TITLE_Y_SPACING: PixelSpacing = 30;
+const PERIOD_TITLE_X_SPACING: PixelSpacing = 10;
+
+const FONT_SIZE: f32 = 20.0;
+
+const ADAPTATION_SET_PADDING: PixelSpacing = 0;
+const ADAPTATION_SET_SPACING: PixelSpacing = 20;
+
+const REPRESENTATION_WIDTH: PixelSpacing = 64;
+const REPRESENTATION_PADDING: PixelSpacing = 5;
+
+const GAP_SIZE: i32 = 50;
+
+/// Distance between the lines shading a gap in a timeline
+const HATCH_SPACING: u32 = 6;
+/// Discontinuities are drawn at least this tall, however short they are
+const MIN_DISCONTINUITY_HEIGHT: u32 = 2;
+
+/// Between the title of a Period and its segments, naming every AdaptationSet and Representation
+const COLUMN_HEADER_HEIGHT: PixelSpacing = 100;
+const HEADER_FONT_SIZE: f32 = 10.0;
+const HEADER_LINE_HEIGHT: i32 = 12;
+
+/// Where presentation time starts on the canvas, the top of the segments of the first Period
+pub(super) const TIMELINE_Y: PixelSpacing =
+    IMAGE_PADDING + PERIOD_TITLE_Y_SPACING + COLUMN_HEADER_HEIGHT;
+
+const LEGEND_SPACING: PixelSpacing = 30;
+const LEGEND_FONT_SIZE: f32 = 12.0;
+const LEGEND_ROW_HEIGHT: PixelSpacing = 20;
+const LEGEND_KEY_WIDTH: PixelSpacing = 24;
+
+/// What a legend entry is drawn with
+enum LegendKey {
+    /// Both colours segments of this type alternate between
+    Segments(ContentType),
+    Fill(Color),
+    Hatched,
+    Outline(Color),
+    Line(Color),
+    /// The short black line closing every run of segments
+    RunEnd,
+}
+
+pub(super) enum Color {
+    AudioSegmentOdd,
+    AudioSegmentEvent,
+    AudioAdaptationBorder,
+    VideoSegmentOdd,
+    VideoSegmentEven,
+    VideoAdaptationBorder,
+    TextSegmentOdd,
+    TextSegmentEven,
+    TextAdaptationBorder,
+    ImageSegmentOdd,
+    ImageSegmentEven,
+    ImageAdaptationBorder,
+    ApplicationSegmentOdd,
+    ApplicationSegmentEven,
+    ApplicationAdaptationBorder,
+    LiveEdge,
+    PresentationEdge,
+    Gap,
+    Overlap,
+    PeriodDuration,
+    Drift,
+}
+
+impl Color {
+    pub fn to_rgba(&self) -> (u8, u8, u8, u8) {
+        match self {
+            Color::AudioSegmentOdd => (144, 190, 109, 255),
+            Color::AudioSegmentEvent => (169, 204, 142, 255),
+            Color::AudioAdaptationBorder => (0, 255, 0, 255),
+            Color::VideoSegmentOdd => (39, 125, 161, 255),
+            Color::VideoSegmentEven => (47, 151, 196, 255),
+            Color::VideoAdaptationBorder => (255, 0, 0, 255),
+            Color::TextSegmentOdd => (243, 114, 44, 255),
+            Color::TextSegmentEven => (248, 150, 30, 255),
+            Color::TextAdaptationBorder => (255, 165, 0, 255),
+            Color::ImageSegmentOdd => (131, 56, 236, 255),
+            Color::ImageSegmentEven => (155, 93, 229, 255),
+            Color::ImageAdaptationBorder => (128, 0, 128, 255),
+            Color::ApplicationSegmentOdd => (87, 117, 144, 255),
+            Color::ApplicationSegmentEven => (119, 141, 169, 255),
+            Color::ApplicationAdaptationBorder => (0, 0, 255, 255),
+            Color::LiveEdge => (220, 20, 60, 255),
+            Color::PresentationEdge => (255, 140, 0, 255),
+            Color::Gap => (230, 230, 230, 255),
+            Color::Overlap => (230, 57, 70, 255),
+            Color::PeriodDuration => (106, 76, 147, 255),
+            Color::Drift => (199, 21, 133, 255),
+        }
+    }
+}
+
+impl ExpandedMpd {
+    /// Lays out every Period side by side, with time running down the canvas at `scale`
+    /// pixels per second. Only positions primitives, drawing them is up to a `Renderer`.
+    pub fn layout(&self, scale: f64, options: &RenderOptions) -> Layout {
+        let debug = options.debug;
+
+        let duration_ms = self.end().saturating_sub(self.start()).as_ms();
+
+        debug!(
+            "Manifest is {}ms long ({} - {}), drawing at {} px/s",
+            duration_ms,
+            self.start_ms(),
+            self.end_ms(),
+            scale
+        );
+
+        let font = FontRef::try_from_slice(include_bytes!("../../fonts/NimbusSanL-Reg.otf"))
+            .unwrap_or_else(|_| panic!("{}", DrawError::CannotCreateFont.describe()));
+
+        let canvas_height = ms_to_pixels(
+            self.timeline_end().saturating_sub(self.start()).as_ms(),
+            scale,
+        ) + TIMELINE_Y
+            + IMAGE_PADDING;
+
+        let mut drawing = DrawQueue::new();
+
+        let mut x_position = IMAGE_PADDING + RULER_WIDTH;
+
+        // Where each Period was placed and how wide it is
+        let mut period_columns: Vec<(PixelSpacing, PixelSpacing)> = vec![];
+
+        let start_timestamp = self.periods.first().expect("No periods").start_ms();
+
+        let boundary_drift = self.boundary_drift(options.drift_threshold);
+
+        for (period_index, period) in self.periods.iter().enumerate() {
+            let period_width = get_period_width(period);
+            let period_height = get_period_height(period, scale);
+
+            // Create a new draw queue, positioned relative to the Period
+            let mut draw_queue = DrawQueue::new();
+
+            let mut y_offset: i32 = 0;
+
+            if period.start() > period.period_start {
+                y_offset = GAP_SIZE;
+
+                draw_queue.queue(DrawTask::FilledRect {
+                    x: 0,
+                    y: 0,
+                    width: period_width,
+                    height: GAP_SIZE as u32,
+                    rgba: Color::Gap.to_rgba(),
+                    tooltip: None,
+                    element: None,
+                });
+
+                let text = &format!(
+                    "{} gap",
+                    &format_duration(period.start().saturating_sub(period.period_start).as_ms())
+                );
+
+                let gap_font_size = 15f32;
+
+                let (text_width, text_height) = text_dimensions(&font, text, gap_font_size);
+
+                let x = period_width.saturating_sub(text_width) / 2;
+                let y = (GAP_SIZE as u32).saturating_sub(text_height) / 2;
+
+                draw_queue.queue(DrawTask::Text {
+                    x: x as i32,
+                    y: y as i32,
+                    scale: gap_font_size,
+                    rgba: (0, 0, 0, 255),
+                    text: text.to_string(),
+                });
+            }
+
+            let y_offset: i32 = y_offset;
+
+            // Track an x offset for all elements in the period
+            let mut x_offset = 0;
+
+            let (_, title_height) = text_dimensions(&font, &period.id, FONT_SIZE);
+
+            for (adaptation_index, adaptation) in period.adaptation_sets.iter().enumerate() {
+                // Padding for the adaptation set
+                x_offset += ADAPTATION_SET_PADDING;
+
+                queue_adaptation_set_header(&mut draw_queue, &font, adaptation, x_offset);
+
+                let adaptation_x = x_offset;
+
+                // Draw all representations
+                for (representation_index, representation) in
+                    adaptation.representations.iter().enumerate()
+                {
+                    let x = x_offset;
+
+                    let width = REPRESENTATION_WIDTH;
+
+                    // Slide the offset with padding
+                    x_offset += width + REPRESENTATION_PADDING;
+
+                    let segment_timeline = representation.segments.segment_timeline();
+
+                    let (column_x, column_width) = (x, width);
+
+                    queue_representation_header(&mut draw_queue, &font, representation, x);
+
+                    let x = x as i32 + 1;
+                    let width = width - 2;
+
+                    let mut i = 0;
+
+                    // Index of the first segment of the current run within the timeline
+                    let mut run_index: u64 = 0;
+
+                    let element =
+                        element_key(&[period_index, adaptation_index, representation_index]);
+
+                    // Positions come from exact times so rounding does not accumulate. Tracks
+                    // starting after the earliest one of the Period are offset by the difference.
+                    let time_to_y = |time: MediaTime| {
+                        y_offset
+                            + ms_to_pixels(time.saturating_sub(period.start()).as_ms(), scale)
+                                as i32
+                    };
+
+                    for segment in &segment_timeline.segments {
+                        let initial_y = time_to_y(segment.start);
+
+                        debug!(
+                            "Draw segment {} {} {} {} x {}ms {} @ {}",
+                            i,
+                            x,
+                            initial_y,
+                            period_height,
+                            segment.segment_duration.as_ms(),
+                            segment.segment_count,
+                            segment.start.as_ms()
+                        );
+
+                        let segment_end_y = time_to_y(segment.end) - 1i32;
+
+                        let segment_height =
+                            ms_to_pixels(segment.segment_duration.as_ms(), scale) as i32;
+
+                        // Too small to tell apart, draw the whole run as one block
+                        if segment.segment_count > 1 && segment_height < MIN_SEGMENT_HEIGHT {
+                            let rgba = segment_color(adaptation.content_type, i);
+
+                            let height = (segment_end_y - initial_y).max(1);
+
+                            draw_queue.queue(DrawTask::FilledRect {
+                                x,
+                                y: initial_y,
+                                width,
+                                height: height as u32,
+                                rgba,
+                                tooltip: Some(describe_segment_run(
+                                    period,
+                                    representation,
+                                    segment,
+                                    run_index,
+                                    None,
+                                )),
+                                element: Some(element.clone()),
+                            });
+
+                            let count_label = format!("{}x", segment.segment_count);
+                            let duration_label = format_segment_duration(segment.segment_duration);
+
+                            let (_, label_height) =
+                                text_dimensions(&font, &count_label, RUN_LABEL_FONT_SIZE);
+
+                            // Two lines of text, only when the block is tall enough
+                            if height > 4 * label_height as i32 {
+                                for (line, text) in
+                                    [count_label, duration_label].into_iter().enumerate()
+                                {
+                                    draw_queue.queue(DrawTask::Text {
+                                        x: x + 2,
+                                        y: initial_y + 2 + (line as i32 * 2 * label_height as i32),
+                                        scale: RUN_LABEL_FONT_SIZE,
+                                        rgba: (0, 0, 0, 255),
+                                        text,
+                                    });
+                                }
+                            }
+
+                            i += 1;
+                        }
+
+                        // Draw each individual segment
+                        for j in 0..segment.segment_count {
+                            if segment.segment_count > 1 && segment_height < MIN_SEGMENT_HEIGHT {
+                                break;
+                            }
+
+                            let y0 = time_to_y(segment.start + segment.segment_duration * j);
+
+                            let y1 = time_to_y(segment.start + segment.segment_duration * (j + 1));
+
+                            let height = y1 - y0;
+
+                            if height < 1 {
+                                debug!("Less than 1px segment");
+                            } else {
+                                draw_queue.queue(DrawTask::FilledRect {
+                                    x,
+                                    y: y0,
+                                    width,
+                                    height: height as u32,
+                                    rgba: segment_color(adaptation.content_type, i),
+                                    tooltip: Some(describe_segment_run(
+                                        period,
+                                        representation,
+                                        segment,
+                                        run_index,
+                                        Some(j),
+                                    )),
+                                    element: Some(element.clone()),
+                                });
+
+                                i += 1;
+                            }
+                        }
+
+                        draw_queue.queue(DrawTask::Line {
+                            start: (x as f32 + (width as f32 / 4.0), segment_end_y as f32),
+                            end: (
+                                x as f32 + width as f32 - 1f32 - (width as f32 / 4.0),
+                                segment_end_y as f32,
+                            ),
+                            rgba: (0, 0, 0, 255),
+                        });
+
+                        run_index += segment.segment_count;
+                    }
+
+                    for discontinuity in segment_timeline.discontinuities() {
+                        queue_discontinuity(
+                            &mut draw_queue,
+                            &font,
+                            &discontinuity,
+                            (x, time_to_y(discontinuity.start())),
+                            (width, time_to_y(discontinuity.end())),
+                            &element,
+                        );
+                    }
+
+                    // Border the AdaptationSet
+
+                    if debug {
+                        let color = match adaptation.content_type {
+                            ContentType::Video => Color::VideoAdaptationBorder,
+                            ContentType::Audio => Color::AudioAdaptationBorder,
+                            ContentType::Text => Color::TextAdaptationBorder,
+                            ContentType::Image => Color::ImageAdaptationBorder,
+                            ContentType::Application => Color::ApplicationAdaptationBorder,
+                        };
+
+                        draw_queue.queue(DrawTask::FilledRect {
+                            x: column_x as i32,
+                            y: y_offset,
+                            width: column_width,
+                            height: period_height.saturating_sub(y_offset as u32),
+                            rgba: color.to_rgba(),
+                            tooltip: None,
+                            element: None,
+                        });
+                    }
+                }
+
+                // Where the AdaptationSet ends against the next Period, when audio and video
+                // drift apart there
+                if let Some(drift) = boundary_drift
+                    .iter()
+                    .find(|boundary| boundary.period_index == period_index && boundary.flagged)
+                    .and_then(|boundary| boundary.adaptation_sets.get(adaptation_index))
+                {
+                    queue_drift_marker(
+                        &mut draw_queue,
+                        &font,
+                        drift,
+                        (adaptation_x, x_offset - REPRESENTATION_PADDING),
+                        y_offset
+                            + ms_to_pixels(drift.end.saturating_sub(period.start()).as_ms(), scale)
+                                as i32,
+                    );
+                }
+
+                x_offset += ADAPTATION_SET_PADDING;
+
+                x_offset += ADAPTATION_SET_SPACING;
+            }
+
+            draw_queue.queue(DrawTask::HollowRect {
+                x: 0,
+                y: 0,
+                width: period_width,
+                height: ms_to_pixels(period.end().saturating_sub(period.start()).as_ms(), scale)
+                    + y_offset as u32,
+                rgba: (0, 0, 0, 255),
+            });
+
+            if let Some(mismatch) = period.duration_mismatch() {
+                let time_to_y = |time: MediaTime| {
+                    y_offset
+                        + ms_to_pixels(time.saturating_sub(period.start()).as_ms(), scale) as i32
+                };
+
+                queue_duration_mismatch(
+                    &mut draw_queue,
+                    &font,
+                    &mismatch,
+                    (time_to_y(mismatch.start()), time_to_y(mismatch.end())),
+                    period_width,
+                    &element_key(&[period_index]),
+                );
+            }
+
+            let y_position =
+                (ms_to_pixels(period.start_ms().saturating_sub(start_timestamp), scale)
+                    + IMAGE_PADDING)
+                    .saturating_sub(y_offset as u32);
+
+            debug!(
+                "Placing period {} ({}x{}) at {} {}",
+                period.id, period_width, period_height, x_position, y_position
+            );
+
+            let title_x = if period_index == 0 {
+                x_position
+            } else {
+                x_position + PERIOD_TITLE_X_SPACING
+            };
+
+            drawing.queue(DrawTask::Text {
+                x: title_x as i32,
+                y: (y_position + title_height / 4) as i32,
+                scale: FONT_SIZE,
+                rgba: (0, 0, 0, 255),
+                text: period.id.clone(),
+            });
+
+            // Where the Period starts and its segments end, next to the title
+            let (title_width, _) = text_dimensions(&font, &period.id, FONT_SIZE);
+
+            let extent = format!(
+                "{} to {}",
+                self.format_time_label(period, period.period_start, options.time_labels),
+                self.format_time_label(period, period.end(), options.time_labels)
+            );
+
+            let (_, extent_height) = text_dimensions(&font, &extent, RULER_FONT_SIZE);
+
+            drawing.queue(DrawTask::Text {
+                x: (title_x + title_width + 8) as i32,
+                y: (y_position + title_height / 4 + title_height) as i32 - extent_height as i32,
+                scale: RULER_FONT_SIZE,
+                rgba: (90, 90, 90, 255),
+                text: extent,
+            });
+
+            draw_queue.translate(
+                x_position as i32,
+                (y_position + PERIOD_TITLE_Y_SPACING + COLUMN_HEADER_HEIGHT) as i32,
+            );
+
+            drawing.append(&mut draw_queue);
+
+            period_columns.push((x_position, period_width));
+
+            x_position += period_width;
+        }
+
+        for (period_index, overlap) in self.period_overlaps() {
+            let (previous_x, _) = period_columns[period_index - 1];
+            let (x, width) = period_columns[period_index];
+
+            let time_to_y = |time: MediaTime| {
+                (ms_to_pixels(time.as_ms().saturating_sub(start_timestamp), scale) + TIMELINE_Y)
+                    as i32
+            };
+
+            queue_period_overlap(
+                &mut drawing,
+                &font,
+                &overlap,
+                (previous_x, x, x + width),
+                (time_to_y(overlap.start()), time_to_y(overlap.end())),
+                &element_key(&[period_index]),
+            );
+        }
+
+        let legend_x = x_position + LEGEND_SPACING;
+
+        let (legend_width, legend_height) =
+            self.queue_legend(&mut drawing, &font, legend_x, options);
+
+        let canvas_width = legend_x + legend_width + IMAGE_PADDING;
+        let canvas_height = canvas_height.max(TIMELINE_Y + legend_height + IMAGE_PADDING);
+
+        self.queue_ruler(&mut drawing, &font, scale, options.time_labels);
+
+        if let Some(live) = &self.live {
+            let mut draw_queue = DrawQueue::new();
+
+            let edges = [
+                (live.live_edge, "live edge", Color::LiveEdge),
+                (
+                    live.presentation_edge(),
+                    "presentation edge",
+                    Color::PresentationEdge,
+                ),
+            ];
+
+            for (edge, label, color) in edges {
+                let rgba = color.to_rgba();
+
+                // Edges outside of the drawn segments, e.g. an empty window, are not drawn
+                if edge < self.start() || edge > self.end() {
+                    continue;
+                }
+
+                let y = (ms_to_pixels(edge.as_ms().saturating_sub(start_timestamp), scale)
+                    + TIMELINE_Y) as f32;
+
+                draw_queue.queue(DrawTask::Line {
+                    start: (0f32, y),
+                    end: (canvas_width as f32, y),
+                    rgba,
+                });
+
+                // Right of the time axis, clear of its labels
+                draw_queue.queue(DrawTask::Text {
+                    x: (IMAGE_PADDING + RULER_WIDTH) as i32,
+                    y: y as i32 - 14,
+                    scale: 12f32,
+                    rgba,
+                    text: format!("{} {}", label, live.to_utc(edge).format("%H:%M:%S%.3fZ")),
+                });
+            }
+
+            drawing.append(&mut draw_queue);
+        }
+
+        debug!("Done");
+
+        Layout {
+            width: canvas_width,
+            height: canvas_height,
+            pixels_per_second: scale,
+            primitives: drawing,
+        }
+    }
+}
+
+impl ExpandedMpd {
+    /// Queues a vertical time axis left of the Periods, with labelled ticks at a round interval
+    /// of the chosen labels and unlabelled ticks between them
+    fn queue_ruler(
+        &self,
+        drawing: &mut DrawQueue,
+        font: &impl Font,
+        scale: f64,
+        time_labels: TimeLabels,
+    ) {
+        let start = self.start();
+        let end = self.end();
+
+        let axis_x = (IMAGE_PADDING + RULER_WIDTH - PERIOD_TITLE_X_SPACING) as f32;
+
+        let time_to_y =
+            |ms: u64| (ms_to_pixels(ms.saturating_sub(start.as_ms()), scale) + TIMELINE_Y) as f32;
+
+        let rgba = (0, 0, 0, 255);
+
+        drawing.queue(DrawTask::Line {
+            start: (axis_x, time_to_y(start.as_ms())),
+            end: (axis_x, time_to_y(end.as_ms())),
+            rgba,
+        });
+
+        let interval_ms = tick_interval(RULER_TICK_SPACING as f64 * 1000.0 / scale) as i64;
+
+        // Only when there is room to tell them apart
+        let minor_interval_ms = Some(interval_ms / 5)
+            .filter(|minor_interval_ms| {
+                *minor_interval_ms > 0 && scale * *minor_interval_ms as f64 >= 4000.0
+            })
+            .unwrap_or(interval_ms);
+
+        for (period_index, period) in self.periods.iter().enumerate() {
+            // Each Period labels the axis down to the start of the next, the last to the end
+            let section_start = if period_index == 0 {
+                start.as_ms()
+            } else {
+                period.period_start.as_ms()
+            };
+
+            let section_end = self
+                .periods
+                .get(period_index + 1)
+                .map_or(end.as_ms() + 1, |next| next.period_start.as_ms());
+
+            let origin = self.label_origin(period, time_labels);
+
+            // Ticks fall on round values of the label, not of the presentation time
+            let mut tick =
+                (origin + section_start as i64).div_euclid(minor_interval_ms) * minor_interval_ms;
+
+            if tick < origin + section_start as i64 {
+                tick += minor_interval_ms;
+            }
+
+            while tick < origin + section_end as i64 {
+                let y = time_to_y((tick - origin) as u64);
+
+                if tick.rem_euclid(interval_ms) == 0 {
+                    drawing.queue(DrawTask::Line {
+                        start: (axis_x - 6.0, y),
+                        end: (axis_x, y),
+                        rgba,
+                    });
+
+                    let text = self.format_label(tick, time_labels, interval_ms < 1000);
+
+                    let (text_width, text_height) = text_dimensions(font, &text, RULER_FONT_SIZE);
+
+                    drawing.queue(DrawTask::Text {
+                        x: (axis_x as u32).saturating_sub(text_width + 10) as i32,
+                        y: y as i32 - text_height as i32 / 2 - 1,
+                        scale: RULER_FONT_SIZE,
+                        rgba,
+                        text,
+                    });
+                } else {
+                    drawing.queue(DrawTask::Line {
+                        start: (axis_x - 3.0, y),
+                        end: (axis_x, y),
+                        rgba,
+                    });
+                }
+
+                tick += minor_interval_ms;
+            }
+        }
+    }
+
+    /// The label of presentation time 0 in milliseconds, for times within `period`
+    fn label_origin(&self, period: &ExpandedPeriod, time_labels: TimeLabels) -> i64 {
+        match (time_labels, &self.live) {
+            (TimeLabels::Utc, Some(live)) => live.availability_start_time.timestamp_millis(),
+            (TimeLabels::Period, _) => -(period.period_start.as_ms() as i64),
+            _ => {
+                -(self
+                    .periods
+                    .first()
+                    .expect("No periods")
+                    .period_start
+                    .as_ms() as i64)
+            }
+        }
+    }
+
+    /// `time` within `period` as the chosen labels show it, with milliseconds when it is not a
+    /// whole second
+    fn format_time_label(
+        &self,
+        period: &ExpandedPeriod,
+        time: MediaTime,
+        time_labels: TimeLabels,
+    ) -> String {
+        let label_ms = self.label_origin(period, time_labels) + time.as_ms() as i64;
+
+        self.format_label(label_ms, time_labels, label_ms % 1000 != 0)
+    }
+
+    /// Wall-clock time for UTC labels of a dynamic manifest, a duration otherwise
+    fn format_label(&self, label_ms: i64, time_labels: TimeLabels, show_ms: bool) -> String {
+        let utc = (time_labels == TimeLabels::Utc && self.live.is_some())
+            .then(|| DateTime::from_timestamp_millis(label_ms))
+            .flatten();
+
+        match utc {
+            Some(utc) if show_ms => utc.format("%H:%M:%S%.3fZ").to_string(),
+            Some(utc) => utc.format("%H:%M:%SZ").to_string(),
+            None => format_clock(label_ms, show_ms),
+        }
+    }
+
+    /// Queues a key to the colours and lines of the layout from `x`, listing only what the
+    /// manifest uses. Returns the size it takes up.
+    fn queue_legend(
+        &self,
+        drawing: &mut DrawQueue,
+        font: &impl Font,
+        x: u32,
+        options: &RenderOptions,
+    ) -> (u32, u32) {
+        let mut entries: Vec<(LegendKey, String)> = vec![];
+
+        let mut content_types: Vec<ContentType> = vec![];
+
+        for adaptation_set in self
+            .periods
+            .iter()
+            .flat_map(|period| &period.adaptation_sets)
+        {
+            if !content_types.contains(&adaptation_set.content_type) {
+                content_types.push(adaptation_set.content_type);
+
+                entries.push((
+                    LegendKey::Segments(adaptation_set.content_type),
+                    format!("{} segments", adaptation_set.content_type.as_str()),
+                ));
+            }
+        }
+
+        entries.push((LegendKey::RunEnd, "end of a run of segments".to_owned()));
+
+        if self
+            .periods
+            .iter()
+            .any(|period| period.start() > period.period_start)
+        {
+            entries.push((
+                LegendKey::Fill(Color::Gap),
+                "Period start before its segments".to_owned(),
+            ));
+        }
+
+        let discontinuities: Vec<Discontinuity> = self
+            .periods
+            .iter()
+            .flat_map(|period| &period.adaptation_sets)
+            .flat_map(|adaptation_set| &adaptation_set.representations)
+            .flat_map(|representation| representation.segments.segment_timeline().discontinuities())
+            .collect();
+
+        let duration_mismatches: Vec<Discontinuity> = self
+            .periods
+            .iter()
+            .filter_map(ExpandedPeriod::duration_mismatch)
+            .collect();
+
+        if discontinuities
+            .iter()
+            .chain(duration_mismatches.iter())
+            .any(|discontinuity| matches!(discontinuity, Discontinuity::Gap { .. }))
+        {
+            entries.push((LegendKey::Hatched, "gap in the segments".to_owned()));
+        }
+
+        if discontinuities
+            .iter()
+            .any(|discontinuity| matches!(discontinuity, Discontinuity::Overlap { .. }))
+        {
+            entries.push((
+                LegendKey::Fill(Color::Overlap),
+                "overlapping segments".to_owned(),
+            ));
+        }
+
+        if !self.period_overlaps().is_empty() {
+            entries.push((
+                LegendKey::Outline(Color::Overlap),
+                "overlapping Periods".to_owned(),
+            ));
+        }
+
+        if !duration_mismatches.is_empty() {
+            entries.push((
+                LegendKey::Line(Color::PeriodDuration),
+                "end of Period@duration".to_owned(),
+            ));
+        }
+
+        if self
+            .boundary_drift(options.drift_threshold)
+            .iter()
+            .any(|boundary| boundary.flagged)
+        {
+            entries.push((
+                LegendKey::Line(Color::Drift),
+                "A/V drift at a Period boundary".to_owned(),
+            ));
+        }
+
+        if self.live.is_some() {
+            entries.push((LegendKey::Line(Color::LiveEdge), "live edge".to_owned()));
+            entries.push((
+                LegendKey::Line(Color::PresentationEdge),
+                "presentation edge".to_owned(),
+            ));
+        }
+
+        let mut width = 0;
+
+        for (row, (key, label)) in entries.iter().enumerate() {
+            let y = (TIMELINE_Y + row as u32 * LEGEND_ROW_HEIGHT) as i32;
+            let key_height = LEGEND_ROW_HEIGHT as i32 - 6;
+
+            match key {
+                LegendKey::Segments(content_type) => {
+                    for index in 0..2 {
+                        drawing.queue(DrawTask::FilledRect {
+                            x: x as i32 + index * LEGEND_KEY_WIDTH as i32 / 2,
+                            y,
+                            width: LEGEND_KEY_WIDTH / 2,
+                            height: key_height as u32,
+                            rgba: segment_color(*content_type, index as usize),
+                            tooltip: None,
+                            element: None,
+                        });
+                    }
+                }
+                LegendKey::Fill(color) => drawing.queue(DrawTask::FilledRect {
+                    x: x as i32,
+                    y,
+                    width: LEGEND_KEY_WIDTH,
+                    height: key_height as u32,
+                    rgba: color.to_rgba(),
+                    tooltip: None,
+                    element: None,
+                }),
+                LegendKey::Hatched => queue_hatched_rect(
+                    drawing,
+                    (x as i32, y),
+                    (LEGEND_KEY_WIDTH, key_height as u32),
+                    None,
+                    None,
+                ),
+                LegendKey::Outline(color) => drawing.queue(DrawTask::HollowRect {
+                    x: x as i32,
+                    y,
+                    width: LEGEND_KEY_WIDTH,
+                    height: key_height as u32,
+                    rgba: color.to_rgba(),
+                }),
+                LegendKey::Line(color) => drawing.queue(DrawTask::Line {
+                    start: (x as f32, (y + key_height / 2) as f32),
+                    end: ((x + LEGEND_KEY_WIDTH) as f32, (y + key_height / 2) as f32),
+                    rgba: color.to_rgba(),
+                }),
+                LegendKey::RunEnd => drawing.queue(DrawTask::Line {
+                    start: (
+                        (x + LEGEND_KEY_WIDTH / 4) as f32,
+                        (y + key_height / 2) as f32,
+                    ),
+                    end: (
+                        (x + LEGEND_KEY_WIDTH * 3 / 4) as f32,
+                        (y + key_height / 2) as f32,
+                    ),
+                    rgba: (0, 0, 0, 255),
+                }),
+            }
+
+            let (label_width, label_height) = text_dimensions(font, label, LEGEND_FONT_SIZE);
+
+            drawing.queue(DrawTask::Text {
+                x: (x + LEGEND_KEY_WIDTH + 6) as i32,
+                y: y + (key_height - label_height as i32) / 2,
+                scale: LEGEND_FONT_SIZE,
+                rgba: (0, 0, 0, 255),
+                text: label.clone(),
+            });
+
+            width = width.max(LEGEND_KEY_WIDTH + 6 + label_width);
+        }
+
+        (width, entries.len() as u32 * LEGEND_ROW_HEIGHT)
+    }
+
+    /// Where the layout ends, the end of the segments or of a Period@duration reaching past them
+    fn timeline_end(&self) -> MediaTime {
+        self.periods
+            .iter()
+            .filter_map(|period| Some(period.period_start + period.period_duration?))
+            .fold(self.end(), MediaTime::max)
+    }
+
+    /// The largest scale, up to the default, at which every Period fits within `max_height`. The
+    /// minimum scale when `max_height` leaves no room below the Period titles and column headers.
+    pub fn fit_pixels_per_second(&self, max_height: PixelSpacing) -> f64 {
+        let duration_ms = self.timeline_end().saturating_sub(self.start()).as_ms();
+
+        let gaps = self
+            .periods
+            .iter()
+            .filter(|period| period.start() > period.period_start)
+            .count() as u32;
+
+        let available = max_height
+            .saturating_sub(TIMELINE_Y + IMAGE_PADDING + 20)
+            .saturating_sub(gaps * GAP_SIZE as u32);
+
+        if duration_ms == 0 {
+            return DEFAULT_PIXELS_PER_SECOND;
+        }
+
+        (available as f64 * 1000.0 / duration_ms as f64)
+            .clamp(MIN_PIXELS_PER_SECOND, DEFAULT_PIXELS_PER_SECOND)
+    }
+}
+
+/// Shades a gap in a timeline or marks an overlap in red, from `start_y` to `end_y` of a
+/// column, labelled with its duration
+fn queue_discontinuity(
+    draw_queue: &mut DrawQueue,
+    font: &impl Font,
+    discontinuity: &Discontinuity,
+    (x, start_y): (i32, i32),
+    (width, end_y): (u32, i32),
+    element: &str,
+) {
+    let height = ((end_y - start_y).max(0) as u32).max(MIN_DISCONTINUITY_HEIGHT);
+
+    let duration = format_segment_duration(discontinuity.duration());
+
+    // Signed like the offsets of the markdown description, the legend tells them apart
+    let (label, tooltip) = match discontinuity {
+        Discontinuity::Gap { .. } => (format!("+{}", duration), "Gap"),
+        Discontinuity::Overlap { .. } => (format!("-{}", duration), "Overlap"),
+    };
+
+    let tooltip = format!(
+        "{} of {} from {} to {}",
+        tooltip,
+        duration,
+        discontinuity.start(),
+        discontinuity.end()
+    );
+
+    match discontinuity {
+        Discontinuity::Gap { .. } => queue_hatched_rect(
+            draw_queue,
+            (x, start_y),
+            (width, height),
+            Some(tooltip),
+            Some(element.to_owned()),
+        ),
+        Discontinuity::Overlap { .. } => draw_queue.queue(DrawTask::FilledRect {
+            x,
+            y: start_y,
+            width,
+            height,
+            rgba: Color::Overlap.to_rgba(),
+            tooltip: Some(tooltip),
+            element: Some(element.to_owned()),
+        }),
+    }
+
+    draw_queue.queue(DrawTask::Text {
+        x: x + 2,
+        y: start_y + 2,
+        scale: HEADER_FONT_SIZE,
+        rgba: (0, 0, 0, 255),
+        text: fit_text(font, &label, HEADER_FONT_SIZE, width - 4),
+    });
+}
+
+/// Marks the end of Period@duration across a Period, shading the time between it and the end of
+/// the segments when they end early. `(start_y, end_y)` spans the mismatch within the Period.
+fn queue_duration_mismatch(
+    draw_queue: &mut DrawQueue,
+    font: &impl Font,
+    mismatch: &Discontinuity,
+    (start_y, end_y): (i32, i32),
+    width: u32,
+    element: &str,
+) {
+    let duration = format_segment_duration(mismatch.duration());
+
+    // Signed like the markdown description, how far the segments end past Period@duration
+    let (duration_end_y, offset) = match mismatch {
+        Discontinuity::Gap { .. } => {
+            queue_hatched_rect(
+                draw_queue,
+                (0, start_y),
+                (
+                    width,
+                    ((end_y - start_y).max(0) as u32).max(MIN_DISCONTINUITY_HEIGHT),
+                ),
+                Some(format!("Segments end {} before Period@duration", duration)),
+                Some(element.to_owned()),
+            );
+
+            (end_y, format!("-{}", duration))
+        }
+        Discontinuity::Overlap { .. } => (start_y, format!("+{}", duration)),
+    };
+
+    let rgba = Color::PeriodDuration.to_rgba();
+
+    for y in [duration_end_y, duration_end_y + 1] {
+        draw_queue.queue(DrawTask::Line {
+            start: (0.0, y as f32),
+            end: (width as f32, y as f32),
+            rgba,
+        });
+    }
+
+    draw_queue.queue(DrawTask::Text {
+        x: 2,
+        y: duration_end_y + 4,
+        scale: HEADER_FONT_SIZE,
+        rgba,
+        text: fit_text(
+            font,
+            &format!("{} vs Period@duration", offset),
+            HEADER_FONT_SIZE,
+            width.saturating_sub(4),
+        ),
+    });
+}
+
+/// Marks the end of an AdaptationSet spanning its columns from `start_x` to `end_x`, labelled
+/// with how far it ends from the start of the next Period
+fn queue_drift_marker(
+    draw_queue: &mut DrawQueue,
+    font: &impl Font,
+    drift: &AdaptationSetDrift,
+    (start_x, end_x): (u32, u32),
+    y: i32,
+) {
+    let rgba = Color::Drift.to_rgba();
+
+    for line_y in [y, y + 1] {
+        draw_queue.queue(DrawTask::Line {
+            start: (start_x as f32 - 4.0, line_y as f32),
+            end: (end_x as f32 + 4.0, line_y as f32),
+            rgba,
+        });
+    }
+
+    draw_queue.queue(DrawTask::Text {
+        x: start_x as i32,
+        y: y + 4,
+        scale: HEADER_FONT_SIZE,
+        rgba,
+        text: fit_text(
+            font,
+            &format!("{} vs next Period", drift.offset),
+            HEADER_FONT_SIZE,
+            end_x - start_x,
+        ),
+    });
+}
+
+/// Outlines in red the time a Period overlaps the previous one, across both from `start_x` to
+/// `end_x`, with a strip on `boundary_x` where the later Period begins
+fn queue_period_overlap(
+    drawing: &mut DrawQueue,
+    font: &impl Font,
+    overlap: &Discontinuity,
+    (start_x, boundary_x, end_x): (u32, u32, u32),
+    (start_y, end_y): (i32, i32),
+    element: &str,
+) {
+    let rgba = Color::Overlap.to_rgba();
+
+    let height = ((end_y - start_y).max(0) as u32).max(MIN_DISCONTINUITY_HEIGHT);
+
+    let duration = format_segment_duration(overlap.duration());
+
+    for inset in 0..2 {
+        drawing.queue(DrawTask::HollowRect {
+            x: start_x as i32 - inset,
+            y: start_y - inset,
+            width: end_x.saturating_sub(start_x) + 2 * inset as u32,
+            height: height + 2 * inset as u32,
+            rgba,
+        });
+    }
+
+    drawing.queue(DrawTask::FilledRect {
+        x: boundary_x as i32 - 2,
+        y: start_y,
+        width: 4,
+        height,
+        rgba,
+        tooltip: Some(format!(
+            "Period starts {} before the segments of the previous Period end, {} to {}",
+            duration,
+            overlap.start(),
+            overlap.end()
+        )),
+        element: Some(element.to_owned()),
+    });
+
+    drawing.queue(DrawTask::Text {
+        x: boundary_x as i32 + 4,
+        y: start_y + 2,
+        scale: HEADER_FONT_SIZE,
+        rgba: (0, 0, 0, 255),
+        text: fit_text(
+            font,
+            &format!("-{} Period overlap", duration),
+            HEADER_FONT_SIZE,
+            end_x.saturating_sub(boundary_x + 6),
+        ),
+    });
+}
+
+/// A light grey rectangle crossed by diagonal lines
+fn queue_hatched_rect(
+    draw_queue: &mut DrawQueue,
+    (x, y): (i32, i32),
+    (width, height): (u32, u32),
+    tooltip: Option<String>,
+    element: Option<String>,
+) {
+    draw_queue.queue(DrawTask::FilledRect {
+        x,
+        y,
+        width,
+        height,
+        rgba: Color::Gap.to_rgba(),
+        tooltip,
+        element,
+    });
+
+    // Each line joins the two edges it crosses, where u + v = k within the rectangle
+    for k in (HATCH_SPACING..width + height).step_by(HATCH_SPACING as usize) {
+        let start = (k.min(width), k - k.min(width));
+        let end = (k - k.min(height), k.min(height));
+
+        draw_queue.queue(DrawTask::Line {
+            start: ((x + start.0 as i32) as f32, (y + start.1 as i32) as f32),
+            end: ((x + end.0 as i32) as f32, (y + end.1 as i32) as f32),
+            rgba: (160, 160, 160, 255),
+        });
+    }
+}
+
+/// Names an AdaptationSet above its columns, starting at `x` within the Period, with its
+/// language and roles below and a line spanning every column under both
+fn queue_adaptation_set_header(
+    draw_queue: &mut DrawQueue,
+    font: &impl Font,
+    adaptation_set: &ExpandedAdaptationSet,
+    x: PixelSpacing,
+) {
+    let width = adaptation_set.representations.len() as u32
+        * (REPRESENTATION_WIDTH + REPRESENTATION_PADDING)
+        - REPRESENTATION_PADDING;
+
+    let name = format!(
+        "AS {} {}",
+        adaptation_set.id,
+        adaptation_set.content_type.as_str()
+    );
+
+    let details: Vec<&str> = adaptation_set
+        .lang
+        .iter()
+        .chain(adaptation_set.roles.iter())
+        .map(String::as_str)
+        .collect();
+
+    let y = -(COLUMN_HEADER_HEIGHT as i32);
+
+    draw_queue.queue(DrawTask::Text {
+        x: x as i32,
+        y: y + 2,
+        scale: HEADER_FONT_SIZE + 1.0,
+        rgba: (0, 0, 0, 255),
+        text: fit_text(font, &name, HEADER_FONT_SIZE + 1.0, width),
+    });
+
+    draw_queue.queue(DrawTask::Text {
+        x: x as i32,
+        y: y + 4 + HEADER_LINE_HEIGHT,
+        scale: HEADER_FONT_SIZE,
+        rgba: (90, 90, 90, 255),
+        text: fit_text(font, &details.join(", "), HEADER_FONT_SIZE, width),
+    });
+
+    let line_y = (y + 2 * HEADER_LINE_HEIGHT + 6) as f32;
+
+    draw_queue.queue(DrawTask::Line {
+        start: (x as f32, line_y),
+        end: ((x + width) as f32, line_y),
+        rgba: (0, 0, 0, 255),
+    });
+}
+
+/// The ID and `summary` of a Representation, one line each, above its column at `x`
+fn queue_representation_header(
+    draw_queue: &mut DrawQueue,
+    font: &impl Font,
+    representation: &ExpandedRepresentation,
+    x: PixelSpacing,
+) {
+    let lines = std::iter::once(&representation.id).chain(representation.summary.iter());
+
+    for (line, text) in lines.enumerate() {
+        draw_queue.queue(DrawTask::Text {
+            x: x as i32,
+            y: -(COLUMN_HEADER_HEIGHT as i32) + (line as i32 + 2) * HEADER_LINE_HEIGHT + 10,
+            scale: HEADER_FONT_SIZE,
+            rgba: if line == 0 {
+                (0, 0, 0, 255)
+            } else {
+                (90, 90, 90, 255)
+            },
+            text: fit_text(font, text, HEADER_FONT_SIZE, REPRESENTATION_WIDTH),
+        });
+    }
+}
+
+/// `text`, shortened with an ellipsis when it is wider than `width`
+fn fit_text(font: &impl Font, text: &str, font_size: f32, width: PixelSpacing) -> String {
+    if text_dimensions(font, text, font_size).0 <= width {
+        return text.to_owned();
+    }
+
+    let mut fitted: String = text.to_owned();
+
+    while !fitted.is_empty() {
+        fitted.pop();
+
+        let shortened = format!("{}…", fitted);
+
+        if text_dimensions(font, &shortened, font_size).0 <= width {
+            return shortened;
+        }
+    }
+
+    String::new()
+}
+
+/// e.g. 0:05, 1:02:03 or 0:01.500 with `show_ms`
+fn format_clock(ms: i64, show_ms: bool) -> String {
+    let sign = if ms < 0 { "-" } else { "" };
+    let ms = ms.unsigned_abs();
+
+    let hours = ms / 3_600_000;
+    let minutes = ms / 60_000 % 60;
+    let seconds = ms / 1000 % 60;
+
+    let mut clock = if hours > 0 {
+        format!("{}{}:{:02}:{:02}", sign, hours, minutes, seconds)
+    } else {
+        format!("{}{}:{:02}", sign, minutes, seconds)
+    };
+
+    if show_ms {
+        clock.push_str(&format!(".{:03}", ms % 1000));
+    }
+
+    clock
+}
+
+fn ms_to_pixels(ms: u64, pixels_per_second: f64) -> u32 {
+    (ms as f64 * pixels_per_second / 1000.0).round() as u32
+}
+
+pub(super) fn segment_color(content_type: ContentType, index: usize) -> (u8, u8, u8, u8) {
+    let even = index.is_multiple_of(2);
+
+    match content_type {
+        ContentType::Audio if even => Color::AudioSegmentEvent,
+        ContentType::Audio => Color::AudioSegmentOdd,
+        ContentType::Video if even => Color::VideoSegmentEven,
+        ContentType::Video => Color::VideoSegmentOdd,
+        ContentType::Text if even => Color::TextSegmentEven,
+        ContentType::Text => Color::TextSegmentOdd,
+        ContentType::Image if even => Color::ImageSegmentEven,
+        ContentType::Image => Color::ImageSegmentOdd,
+        ContentType::Application if even => Color::ApplicationSegmentEven,
+        ContentType::Application => Color::ApplicationSegmentOdd,
+    }
+    .to_rgba()
+}
+
+/// Tooltip text for one segment of `run`, or the whole run when `segment` is `None`, with its
+/// position in both presentation time and the timescale of the manifest. `run_index` is the
+/// index of the first segment of the run within the Representation.
+fn describe_segment_run(
+    period: &ExpandedPeriod,
+    representation: &ExpandedRepresentation,
+    run: &ExpandedSegmentTimelineSegment,
+    run_index: u64,
+    segment: Option<u64>,
+) -> String {
+    let segment_duration = run.segment_duration;
+    let timescale = segment_duration.timescale;
+
+    let (start, index, count) = match segment {
+        Some(j) => (run.start + segment_duration * j, run_index + j, 1),
+        None => (run.start, run_index, run.segment_count),
+    };
+
+    let t = start
+        .saturating_sub(period.period_start)
+        .to_timescale(timescale)
+        + run.presentation_time_offset;
+
+    let summary = if count == 1 {
+        format!("Segment {} at {}, {} long", index, start, segment_duration)
+    } else {
+        format!(
+            "Segments {}-{} from {}, {} each",
+            index,
+            index + count - 1,
+            start,
+            segment_duration
+        )
+    };
+
+    let mut description = format!(
+        "{}\n$Time$={} d={} timescale={}",
+        summary, t, segment_duration.ticks, timescale
+    );
+
+    if let Some(number) = representation.segment_number(index) {
+        if count == 1 {
+            description.push_str(&format!(" $Number$={}", number));
+        } else {
+            description.push_str(&format!(" $Number$={}-{}", number, number + count - 1));
+        }
+    }
+
+    if count == 1 {
+        if let Some(url) = representation.segment_url(index, t) {
+            description.push_str(&format!("\n{}", url));
+        }
+    }
+
+    description
+}
+
+/// Compact duration for run labels and terminal ticks, e.g. 2s or 1.92s
+pub(super) fn format_segment_duration(duration: MediaTime) -> String {
+    format!("{}s", duration.as_ms() as f64 / 1000.0)
+}
+
+fn get_period_height(period: &ExpandedPeriod, scale: f64) -> u32 {
+    debug!(
+        "Calc period height {} {:?} {} - {}",
+        period.period_start.as_ms(),
+        period.period_duration.map(|duration| duration.as_ms()),
+        period.end_ms(),
+        period.start_ms()
+    );
+    let duration_ms = period.end().saturating_sub(period.start()).as_ms();
+
+    let height = ms_to_pixels(duration_ms, scale);
+
+    if period.start() > period.period_start {
+        height + GAP_SIZE as u32
+    } else {
+        height
+    }
+}
+
+fn format_duration(duration_ms: u64) -> String {
+    let mut remaining_ms = duration_ms;
+    let mut result = String::new();
+
+    let years = remaining_ms / (1000 * 60 * 60 * 24 * 365);
+    if years > 0 {
+        result.push_str(&format!("{}yr ", years));
+        remaining_ms %= 1000 * 60 * 60 * 24 * 365;
+    }
+
+    let months = remaining_ms / (1000 * 60 * 60 * 24 * 30);
+    if months > 0 {
+        result.push_str(&format!("{}mo ", months));
+        remaining_ms %= 1000 * 60 * 60 * 24 * 30;
+    }
+
+    let days = remaining_ms / (1000 * 60 * 60 * 24);
+    if days > 0 {
+        result.push_str(&format!("{}day ", days));
+        remaining_ms %= 1000 * 60 * 60 * 24;
+    }
+
+    let hours = remaining_ms / (1000 * 60 * 60);
+    if hours > 0 {
+        result.push_str(&format!("{}hr ", hours));
+        remaining_ms %= 1000 * 60 * 60;
+    }
+
+    let minutes = remaining_ms / (1000 * 60);
+    if minutes > 0 {
+        result.push_str(&format!("{}min ", minutes));
+        remaining_ms %= 1000 * 60;
+    }
+
+    let seconds = remaining_ms / 1000;
+    remaining_ms %= 1000;
+
+    if seconds > 0 {
+        result.push_str(&format!("{}.{}ms ", seconds, remaining_ms));
+    } else if remaining_ms > 0 {
+        result.push_str(&format!("{}ms ", remaining_ms));
+    }
+
+    result.trim().to_string()
+}
+
+/// Estimates the size of a given `text` string at `font_size` in `font``.
+/// ImageProc does not expose the expected size of draw_text_mut so this function is copied from source
+/// https://github.com/image-rs/imageproc/blob/master/src/drawing/text.rs#L10-L37
+fn text_dimensions(font: &impl Font, text: &str, font_size: f32) -> (u32, u32) {
+    let scale = PxScale::from(font_size);
+
+    let (mut w, mut h) = (0f32, 0f32);
+
+    let font = font.as_scaled(scale);
+    let mut last: Option<GlyphId> = None;
+
+    for c in text.chars() {
+        let glyph_id = font.glyph_id(c);
+        let glyph = glyph_id.with_scale_and_position(scale, point(w, font.ascent()));
+        w += font.h_advance(glyph_id);
+        if let Some(g) = font.outline_glyph(glyph) {
+            if let Some(last) = last {
+                w += font.kern(glyph_id, last);
+            }
+            last = Some(glyph_id);
+            let bb = g.px_bounds();
+            h = h.max(bb.height());
+        }
+    }
+
+    (w as u32, h as u32)
+}
+
+fn get_period_width(period: &ExpandedPeriod) -> u32 {
+    let mut width = 0u32;
+
+    for adaptation_set in &period.adaptation_sets {
+        width += 2 * ADAPTATION_SET_PADDING;
+
+        for _representation in &adaptation_set.representations {
+            width += REPRESENTATION_WIDTH + REPRESENTATION_PADDING;
+        }
+
+        width += ADAPTATION_SET_SPACING;
+    }
+
+    // Remove the trailing spacer
+    width -= ADAPTATION_SET_SPACING;
+    width -= REPRESENTATION_PADDING;
+
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{expanded::ParseMode, util::fixture};
+
+    const SCALE: f64 = DEFAULT_PIXELS_PER_SECOND;
+
+    /// Two 6s Periods of a single video Representation, each with `segments`
+    fn manifest(segments: &str) -> ExpandedMpd {
+        let template = format!(
+            r#"<SegmentTemplate timescale="1000" media="$Time$.m4s"><SegmentTimeline>{}</SegmentTimeline></SegmentTemplate>"#,
+            segments
+        );
+
+        fixture::manifest()
+            .mpd_attribute("mediaPresentationDuration", "PT12S")
+            .period_attribute("duration", "PT6S")
+            .adaptation_set_child(&template)
+            .period("p1", "PT6S")
+            .period_attribute("duration", "PT6S")
+            .adaptation_set_child(&template)
+            .expand(ParseMode::Default)
+            .unwrap()
+    }
+
+    fn layout(mpd: &ExpandedMpd) -> Layout {
+        let options = RenderOptions {
+            debug: false,
+            time_labels: TimeLabels::Media,
+            pixels_per_second: Some(SCALE),
+            max_height: DEFAULT_MAX_HEIGHT,
+            drift_threshold: MediaTime::from_ms(100),
+        };
+
+        mpd.layout(SCALE, &options)
+    }
+
+    /// Where each Text task reading `text` is placed
+    fn text_positions(layout: &Layout, text: &str) -> Vec<(i32, i32)> {
+        layout
+            .primitives
+            .tasks()
+            .iter()
+            .filter_map(|task| match task {
+                DrawTask::Text { x, y, text: t, .. } if t == text => Some((*x, *y)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn periods_side_by_side_and_down_in_time() {
+        let mpd = manifest(r#"<S t="0" d="2000" r="2"/>"#);
+        let layout = layout(&mpd);
+
+        let [(p0_x, p0_y)] = text_positions(&layout, "p0")[..] else {
+            panic!("Expecting a single title for p0");
+        };
+        let [(p1_x, p1_y)] = text_positions(&layout, "p1")[..] else {
+            panic!("Expecting a single title for p1");
+        };
+
+        assert_eq!(p0_x, (IMAGE_PADDING + RULER_WIDTH) as i32);
+        assert_eq!(
+            p1_x,
+            p0_x + (get_period_width(&mpd.periods[0]) + PERIOD_TITLE_X_SPACING) as i32
+        );
+
+        // The second Period starts 6s down
+        assert_eq!(p1_y - p0_y, ms_to_pixels(6000, SCALE) as i32);
+    }
+
+    #[test]
+    fn short_segments_collapse_into_a_labelled_run() {
+        // 40ms segments are under 2px at 40px/s
+        let mpd = manifest(r#"<S t="0" d="40" r="149"/>"#);
+        let layout = layout(&mpd);
+
+        assert_eq!(text_positions(&layout, "150x").len(), 2);
+
+        // A single block per Representation rather than one per segment
+        let blocks = layout
+            .primitives
+            .tasks()
+            .iter()
+            .filter(|task| {
+                matches!(task, DrawTask::FilledRect { element: Some(element), tooltip: Some(_), .. } if element == "0/0/0")
+            })
+            .count();
+
+        assert_eq!(blocks, 1);
+    }
+
+    #[test]
+    fn fit_pixels_per_second_within_max_height() {
+        let mpd = manifest(r#"<S t="0" d="2000" r="2"/>"#);
+
+        // 240px for 12s
+        let max_height = TIMELINE_Y + IMAGE_PADDING + 20 + 240;
+
+        assert_eq!(mpd.fit_pixels_per_second(max_height), 20.0);
+        assert_eq!(
+            mpd.fit_pixels_per_second(DEFAULT_MAX_HEIGHT),
+            DEFAULT_PIXELS_PER_SECOND
+        );
+    }
+
+    #[test]
+    fn fit_pixels_per_second_below_the_headers() {
+        let mpd = manifest(r#"<S t="0" d="2000" r="2"/>"#);
+
+        assert_eq!(mpd.fit_pixels_per_second(TIMELINE_Y), MIN_PIXELS_PER_SECOND);
+    }
+
+    #[test]
+    fn ruler_labels_round_intervals() {
+        let mpd = manifest(r#"<S t="0" d="2000" r="2"/>"#);
+        let layout = layout(&mpd);
+
+        // 60px apart at 40px/s is 1.5s, rounded up to 2s
+        for (seconds, label) in [(0, "0:00"), (2, "0:02"), (6, "0:06"), (12, "0:12")] {
+            let [(x, y)] = text_positions(&layout, label)[..] else {
+                panic!("Expecting a single tick labelled {}", label);
+            };
+
+            assert!(x < (IMAGE_PADDING + RULER_WIDTH) as i32);
+
+            let tick_y = (TIMELINE_Y + ms_to_pixels(seconds * 1000, SCALE)) as i32;
+
+            assert!(
+                (y - tick_y).abs() < 10,
+                "{} at {}, tick at {}",
+                label,
+                y,
+                tick_y
+            );
+        }
+
+        assert!(text_positions(&layout, "0:01").is_empty());
+    }
+}
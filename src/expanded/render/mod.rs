@@ -0,0 +1,94 @@
+mod draw_queue;
+mod html;
+mod layout;
+mod png;
+mod svg;
+mod terminal;
+
+use draw_queue::DrawQueue;
+
+use super::MediaTime;
+
+pub use layout::{DEFAULT_MAX_HEIGHT, DEFAULT_PIXELS_PER_SECOND};
+
+type PixelSpacing = u32;
+
+/// What the labels of the time axis count from
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ArgEnum)]
+pub enum TimeLabels {
+    /// Presentation time since the start of the first Period
+    #[default]
+    Media,
+    /// Time since the start of the Period the tick falls in
+    Period,
+    /// Wall-clock time of dynamic manifests, media time otherwise
+    Utc,
+}
+
+pub struct RenderOptions {
+    pub debug: bool,
+    pub time_labels: TimeLabels,
+    /// Overrides the automatic time scale
+    pub pixels_per_second: Option<f64>,
+    /// The automatic time scale of raster output is reduced until the image fits this height
+    pub max_height: PixelSpacing,
+    /// Period boundaries where audio and video end further apart than this are marked
+    pub drift_threshold: MediaTime,
+}
+
+/// Every primitive of a manifest, positioned on a single canvas by the layout pass
+pub struct Layout {
+    pub width: u32,
+    pub height: u32,
+    /// The time scale it was laid out at
+    pub pixels_per_second: f64,
+    primitives: DrawQueue,
+}
+
+/// Identifies an element of the expanded model by its position, e.g. 0/1/2 for the third
+/// Representation of the second AdaptationSet of the first Period
+pub fn element_key(indices: &[usize]) -> String {
+    indices
+        .iter()
+        .map(|index| index.to_string())
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+// Round intervals of a second or more, so ticks land on whole minutes and hours
+const CLOCK_INTERVALS_S: [f64; 17] = [
+    1.0, 2.0, 5.0, 10.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 900.0, 1800.0, 3600.0, 7200.0,
+    21600.0, 43200.0, 86400.0,
+];
+
+/// The smallest round interval in milliseconds of at least `min_ms`, used to space the ticks of
+/// a time axis. Below a second it is 1, 2 or 5 times a power of ten, above it a clock interval.
+fn tick_interval(min_ms: f64) -> f64 {
+    if min_ms < 1000.0 {
+        let mut magnitude = 1.0;
+
+        loop {
+            for step in [1.0, 2.0, 5.0] {
+                if step * magnitude >= min_ms {
+                    return step * magnitude;
+                }
+            }
+
+            magnitude *= 10.0;
+        }
+    }
+
+    CLOCK_INTERVALS_S
+        .iter()
+        .map(|interval_s| interval_s * 1000.0)
+        .find(|interval_ms| *interval_ms >= min_ms)
+        // Whole days beyond that
+        .unwrap_or_else(|| (min_ms / 86_400_000.0).ceil() * 86_400_000.0)
+}
+
+/// A backend turning a positioned `Layout` into some output, e.g. an image or a document
+pub trait Renderer {
+    type Output;
+
+    fn render(&self, layout: &Layout) -> Self::Output;
+}
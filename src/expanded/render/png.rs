@@ -0,0 +1,81 @@
+use ab_glyph::FontRef;
+use image::{ImageBuffer, Rgba};
+use imageproc::{
+    drawing::{draw_filled_rect_mut, draw_hollow_rect_mut, draw_line_segment_mut, draw_text_mut},
+    rect::Rect,
+};
+
+use super::{draw_queue::DrawTask, Layout, RenderOptions, Renderer};
+use crate::{expanded::ExpandedMpd, util::error::DrawError};
+
+/// Rasterises a layout onto a white canvas
+pub struct PngRenderer;
+
+impl Renderer for PngRenderer {
+    type Output = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+    fn render(&self, layout: &Layout) -> Self::Output {
+        let font = FontRef::try_from_slice(include_bytes!("../../fonts/NimbusSanL-Reg.otf"))
+            .unwrap_or_else(|_| panic!("{}", DrawError::CannotCreateFont.describe()));
+
+        let mut img: ImageBuffer<Rgba<u8>, Vec<u8>> =
+            ImageBuffer::from_pixel(layout.width, layout.height, Rgba([255, 255, 255, 255]));
+
+        for task in layout.primitives.tasks() {
+            match task {
+                DrawTask::FilledRect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    rgba,
+                    ..
+                } => {
+                    let rect = Rect::at(*x, *y).of_size(*width, *height);
+
+                    draw_filled_rect_mut(&mut img, rect, to_rgba(*rgba));
+                }
+                DrawTask::HollowRect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    rgba,
+                } => {
+                    let rect = Rect::at(*x, *y).of_size(*width, *height);
+
+                    draw_hollow_rect_mut(&mut img, rect, to_rgba(*rgba));
+                }
+                DrawTask::Text {
+                    x,
+                    y,
+                    scale,
+                    rgba,
+                    text,
+                } => {
+                    draw_text_mut(&mut img, to_rgba(*rgba), *x, *y, *scale, &font, text);
+                }
+                DrawTask::Line { start, end, rgba } => {
+                    draw_line_segment_mut(&mut img, *start, *end, to_rgba(*rgba));
+                }
+            }
+        }
+
+        img
+    }
+}
+
+fn to_rgba((r, g, b, a): (u8, u8, u8, u8)) -> Rgba<u8> {
+    Rgba([r, g, b, a])
+}
+
+impl ExpandedMpd {
+    /// Rasterises the layout. Unless a scale is given it is reduced to fit `max_height`.
+    pub fn to_png(&self, options: &RenderOptions) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        let scale = options
+            .pixels_per_second
+            .unwrap_or_else(|| self.fit_pixels_per_second(options.max_height));
+
+        PngRenderer.render(&self.layout(scale, options))
+    }
+}
@@ -0,0 +1,189 @@
+(function () {
+  const timeline = document.getElementById('timeline');
+  const svg = timeline.querySelector('svg');
+  const tooltip = document.getElementById('tooltip');
+
+  const baseWidth = svg.viewBox.baseVal.width;
+  const baseHeight = svg.viewBox.baseVal.height;
+
+  // Time runs down the canvas, so zooming only stretches the vertical axis
+  svg.setAttribute('preserveAspectRatio', 'none');
+  svg.style.width = baseWidth + 'px';
+
+  const texts = Array.from(svg.querySelectorAll('text'));
+
+  let zoom = 1;
+
+  function applyZoom(next, anchorY) {
+    next = Math.min(Math.max(next, 0.1), 200);
+
+    // Keep the time under the anchor in place
+    const anchor = anchorY === undefined ? timeline.clientHeight / 2 : anchorY;
+    const time = (timeline.scrollTop + anchor) / zoom;
+
+    zoom = next;
+    svg.style.height = baseHeight * zoom + 'px';
+
+    // Undo the stretch on labels so they stay readable
+    for (const text of texts) {
+      const y = text.getAttribute('y');
+      text.setAttribute('transform', `translate(0 ${y}) scale(1 ${1 / zoom}) translate(0 ${-y})`);
+    }
+
+    timeline.scrollTop = time * zoom - anchor;
+  }
+
+  applyZoom(1);
+
+  timeline.addEventListener('wheel', (event) => {
+    if (!event.ctrlKey && !event.metaKey) {
+      return;
+    }
+
+    event.preventDefault();
+
+    const anchor = event.clientY - timeline.getBoundingClientRect().top;
+
+    applyZoom(zoom * Math.exp(-event.deltaY / 300), anchor);
+  }, { passive: false });
+
+  document.querySelectorAll('[data-zoom]').forEach((button) => {
+    button.addEventListener('click', () => {
+      switch (button.dataset.zoom) {
+        case 'in': applyZoom(zoom * 1.5); break;
+        case 'out': applyZoom(zoom / 1.5); break;
+        default: applyZoom(1);
+      }
+    });
+  });
+
+  // Drag to pan
+  let drag = null;
+
+  timeline.addEventListener('mousedown', (event) => {
+    drag = {
+      x: event.clientX,
+      y: event.clientY,
+      left: timeline.scrollLeft,
+      top: timeline.scrollTop,
+      moved: false,
+    };
+  });
+
+  window.addEventListener('mousemove', (event) => {
+    if (!drag) {
+      return;
+    }
+
+    const dx = event.clientX - drag.x;
+    const dy = event.clientY - drag.y;
+
+    if (Math.abs(dx) + Math.abs(dy) > 3) {
+      drag.moved = true;
+      timeline.classList.add('panning');
+    }
+
+    timeline.scrollLeft = drag.left - dx;
+    timeline.scrollTop = drag.top - dy;
+  });
+
+  window.addEventListener('mouseup', () => {
+    timeline.classList.remove('panning');
+
+    // Let the click handler know whether this was a drag
+    setTimeout(() => { drag = null; });
+  });
+
+  // Tooltips replace the native <title> ones, which are slow to appear
+  svg.querySelectorAll('rect > title').forEach((title) => {
+    title.parentNode.dataset.tooltip = title.textContent;
+    title.remove();
+  });
+
+  svg.addEventListener('mousemove', (event) => {
+    const text = event.target.dataset && event.target.dataset.tooltip;
+
+    if (!text || (drag && drag.moved)) {
+      tooltip.style.display = 'none';
+      return;
+    }
+
+    tooltip.textContent = text;
+    tooltip.style.display = 'block';
+
+    const x = Math.min(event.clientX + 12, window.innerWidth - tooltip.offsetWidth - 4);
+    const y = Math.min(event.clientY + 12, window.innerHeight - tooltip.offsetHeight - 4);
+
+    tooltip.style.left = x + 'px';
+    tooltip.style.top = y + 'px';
+  });
+
+  svg.addEventListener('mouseleave', () => {
+    tooltip.style.display = 'none';
+  });
+
+  // Selecting an element highlights it in the timeline, the panels and the source
+  function select(key, openPanel) {
+    document.querySelectorAll('.selected').forEach((el) => el.classList.remove('selected'));
+    document.querySelectorAll('.highlighted').forEach((el) => el.classList.remove('highlighted'));
+
+    svg.querySelectorAll('rect[data-element]').forEach((rect) => {
+      const element = rect.dataset.element;
+
+      if (element === key || element.startsWith(key + '/')) {
+        rect.classList.add('selected');
+      }
+    });
+
+    const panel = document.querySelector(`#elements details[data-element="${key}"]`);
+
+    if (panel) {
+      panel.classList.add('selected');
+
+      // A clicked summary toggles its own panel
+      const first = openPanel ? panel : panel.parentElement;
+
+      for (let parent = first; parent && parent.tagName === 'DETAILS'; parent = parent.parentElement) {
+        parent.open = true;
+      }
+    }
+
+    const lines = SOURCE_LINES[key];
+
+    if (lines) {
+      for (let line = lines[0]; line <= lines[1]; line++) {
+        const el = document.getElementById('L' + line);
+
+        if (el) {
+          el.classList.add('highlighted');
+        }
+      }
+
+      const first = document.getElementById('L' + lines[0]);
+
+      if (first) {
+        first.scrollIntoView({ block: 'center' });
+      }
+    }
+  }
+
+  svg.addEventListener('click', (event) => {
+    const key = event.target.dataset && event.target.dataset.element;
+
+    if (key && !(drag && drag.moved)) {
+      select(key, true);
+
+      const panel = document.querySelector(`#elements details[data-element="${key}"]`);
+
+      if (panel) {
+        panel.scrollIntoView({ block: 'nearest' });
+      }
+    }
+  });
+
+  document.querySelectorAll('#elements summary').forEach((summary) => {
+    summary.addEventListener('click', () => {
+      select(summary.parentElement.dataset.element, false);
+    });
+  });
+})();
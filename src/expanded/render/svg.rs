@@ -0,0 +1,132 @@
+use super::{draw_queue::DrawTask, Layout, RenderOptions, Renderer, DEFAULT_PIXELS_PER_SECOND};
+use crate::{expanded::ExpandedMpd, push_fmt, push_line, util::xml::escape};
+
+// Matches the embedded NimbusSanL font used for raster output
+const FONT_FAMILY: &str = "'Nimbus Sans L', Helvetica, Arial, sans-serif";
+
+/// Emits a layout as an SVG document, with segment tooltips as `<title>` elements
+pub struct SvgRenderer;
+
+impl Renderer for SvgRenderer {
+    type Output = String;
+
+    fn render(&self, layout: &Layout) -> Self::Output {
+        let mut svg = String::new();
+
+        push_line!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            layout.width,
+            layout.height
+        );
+        push_line!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+
+        for task in layout.primitives.tasks() {
+            match task {
+                DrawTask::FilledRect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    rgba,
+                    tooltip,
+                    element,
+                } => {
+                    push_fmt!(
+                        svg,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}""#,
+                        x,
+                        y,
+                        width,
+                        height,
+                        to_svg_color(*rgba)
+                    );
+
+                    if let Some(element) = element {
+                        push_fmt!(svg, r#" data-element="{}""#, escape(element));
+                    }
+
+                    match tooltip {
+                        Some(tooltip) => {
+                            push_line!(svg, "><title>{}</title></rect>", escape(tooltip));
+                        }
+                        None => {
+                            push_line!(svg, "/>");
+                        }
+                    }
+                }
+                DrawTask::HollowRect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    rgba,
+                } => {
+                    // Inset by half a pixel so the 1px stroke covers the same pixels as the raster
+                    push_line!(
+                        svg,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}" stroke-width="1"/>"#,
+                        *x as f32 + 0.5,
+                        *y as f32 + 0.5,
+                        width.saturating_sub(1),
+                        height.saturating_sub(1),
+                        to_svg_color(*rgba)
+                    );
+                }
+                DrawTask::Text {
+                    x,
+                    y,
+                    scale,
+                    rgba,
+                    text,
+                } => {
+                    push_line!(
+                        svg,
+                        r#"<text x="{}" y="{}" font-size="{}" font-family="{}" dominant-baseline="hanging" fill="{}">{}</text>"#,
+                        x,
+                        y,
+                        scale,
+                        FONT_FAMILY,
+                        to_svg_color(*rgba),
+                        escape(text)
+                    );
+                }
+                DrawTask::Line { start, end, rgba } => {
+                    push_line!(
+                        svg,
+                        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1"/>"#,
+                        start.0,
+                        start.1 + 0.5,
+                        end.0,
+                        end.1 + 0.5,
+                        to_svg_color(*rgba)
+                    );
+                }
+            }
+        }
+
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+}
+
+impl ExpandedMpd {
+    /// Emits the layout as SVG. Vector output has no size limit, so the default scale is kept
+    /// however long the manifest is.
+    pub fn to_svg(&self, options: &RenderOptions) -> String {
+        let scale = options
+            .pixels_per_second
+            .unwrap_or(DEFAULT_PIXELS_PER_SECOND);
+
+        SvgRenderer.render(&self.layout(scale, options))
+    }
+}
+
+fn to_svg_color((r, g, b, a): (u8, u8, u8, u8)) -> String {
+    if a == 255 {
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    } else {
+        format!("rgba({},{},{},{:.3})", r, g, b, a as f32 / 255.0)
+    }
+}
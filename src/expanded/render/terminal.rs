@@ -0,0 +1,405 @@
+use super::{
+    draw_queue::DrawTask,
+    element_key,
+    layout::{format_segment_duration, segment_color, Color, TIMELINE_Y},
+    Layout, RenderOptions, Renderer,
+};
+use crate::expanded::{ContentType, Expanded, ExpandedMpd, MediaTime};
+use crate::{push_fmt, push_line};
+
+/// Columns taken by the labels in front of every row
+const LABEL_WIDTH: usize = 16;
+/// Fewest columns between two ticks of the time axis
+const MIN_TICK_SPACING: usize = 10;
+/// Runs of segments shorter than a column are laid out as a single block
+const PIXELS_PER_COLUMN: f64 = 4.0;
+
+const RESET: &str = "\x1b[0m";
+
+/// What one column of a Representation row shows
+#[derive(Clone, Copy, PartialEq)]
+enum Cell {
+    Empty,
+    Segment(ContentType, usize),
+    Gap,
+    Overlap,
+}
+
+impl Cell {
+    fn symbol(&self, color: bool) -> char {
+        match self {
+            Cell::Empty => ' ',
+            // Without colour, alternate segments are told apart by their shade
+            Cell::Segment(_, index) if !color && index % 2 == 1 => '▓',
+            Cell::Segment(..) => '█',
+            Cell::Gap => '░',
+            Cell::Overlap if color => '▓',
+            Cell::Overlap => '!',
+        }
+    }
+
+    fn rgba(&self) -> Option<(u8, u8, u8, u8)> {
+        match self {
+            Cell::Empty => None,
+            Cell::Segment(content_type, index) => Some(segment_color(*content_type, *index)),
+            Cell::Gap => Some(Color::Gap.to_rgba()),
+            Cell::Overlap => Some(Color::Overlap.to_rgba()),
+        }
+    }
+
+    /// Where rectangles share a column, overlaps win over segments and segments over gaps
+    fn rank(&self) -> u8 {
+        match self {
+            Cell::Empty => 0,
+            Cell::Gap => 1,
+            Cell::Segment(..) => 2,
+            Cell::Overlap => 3,
+        }
+    }
+}
+
+/// Draws every Representation as a row of block characters on a time axis fitted to `width`
+/// columns, grouped by Period and AdaptationSet. Each column shows the rectangles the layout
+/// placed for the Representation at its middle. With `color`, segments use the same palette as
+/// the image output through ANSI escapes.
+pub struct TerminalRenderer<'a> {
+    pub mpd: &'a ExpandedMpd,
+    pub width: usize,
+    pub color: bool,
+}
+
+impl TerminalRenderer<'_> {
+    fn columns(&self) -> usize {
+        self.width.saturating_sub(LABEL_WIDTH + 1).max(10)
+    }
+
+    fn ms_per_column(&self) -> f64 {
+        let duration_ms = self.mpd.end().saturating_sub(self.mpd.start()).as_ms();
+
+        (duration_ms as f64 / self.columns() as f64).max(1.0)
+    }
+
+    /// The layout's rectangles for the Representation `key`, each painted over the columns
+    /// whose middle it covers. Nothing is addressed between `period_start` and the first of them
+    /// either, the layout leaves that to the Period so it is shaded as a gap here.
+    fn cells(
+        &self,
+        layout: &Layout,
+        key: &str,
+        content_type: ContentType,
+        period_start: MediaTime,
+    ) -> Vec<Cell> {
+        let columns = self.columns();
+        let pixels_per_column = self.ms_per_column() * layout.pixels_per_second / 1000.0;
+
+        // The first column whose middle lies at or below `y`
+        let column_at = |y: i32| {
+            let column = ((y as f64 - TIMELINE_Y as f64) / pixels_per_column - 0.5).ceil();
+
+            column.clamp(0.0, columns as f64) as usize
+        };
+
+        let mut cells = vec![Cell::Empty; columns];
+        let mut segment_index = 0;
+        let mut first_y = None;
+
+        for task in layout.primitives.tasks() {
+            let DrawTask::FilledRect {
+                y,
+                height,
+                rgba,
+                element: Some(element),
+                ..
+            } = task
+            else {
+                continue;
+            };
+
+            if element != key {
+                continue;
+            }
+
+            first_y.get_or_insert(*y);
+
+            let cell = if *rgba == Color::Gap.to_rgba() {
+                Cell::Gap
+            } else if *rgba == Color::Overlap.to_rgba() {
+                Cell::Overlap
+            } else {
+                segment_index += 1;
+
+                Cell::Segment(content_type, segment_index - 1)
+            };
+
+            for current in &mut cells[column_at(*y)..column_at(*y + *height as i32)] {
+                if cell.rank() >= current.rank() {
+                    *current = cell;
+                }
+            }
+        }
+
+        if let Some(first_y) = first_y {
+            let start_ms = period_start.as_ms().saturating_sub(self.mpd.start_ms());
+            let start_y = TIMELINE_Y as f64 + start_ms as f64 * layout.pixels_per_second / 1000.0;
+
+            for current in &mut cells[column_at(start_y.round() as i32)..column_at(first_y)] {
+                if *current == Cell::Empty {
+                    *current = Cell::Gap;
+                }
+            }
+        }
+
+        cells
+    }
+
+    /// Tick labels every few columns, at a round number of seconds
+    fn write_time_axis(&self, text: &mut String) {
+        let columns = self.columns();
+        let ms_per_column = self.ms_per_column();
+        let start_ms = self.mpd.start_ms() as f64;
+
+        let interval_ms = super::tick_interval(ms_per_column * MIN_TICK_SPACING as f64);
+
+        let mut labels = vec![' '; columns];
+        let mut ticks = vec!['─'; columns];
+
+        let first_tick = (start_ms / interval_ms).ceil() as u64;
+
+        for tick in first_tick.. {
+            let tick_ms = tick as f64 * interval_ms;
+            let column = ((tick_ms - start_ms) / ms_per_column) as usize;
+
+            if column >= columns {
+                break;
+            }
+
+            ticks[column] = '┬';
+
+            let label = format_segment_duration(MediaTime::from_ms(tick_ms.round() as u64));
+
+            for (offset, c) in label.chars().enumerate() {
+                if let Some(label) = labels.get_mut(column + offset) {
+                    *label = c;
+                }
+            }
+        }
+
+        push_line!(
+            text,
+            "{}{}",
+            " ".repeat(LABEL_WIDTH + 1),
+            labels.into_iter().collect::<String>()
+        );
+        push_line!(
+            text,
+            "{}{}",
+            " ".repeat(LABEL_WIDTH + 1),
+            ticks.into_iter().collect::<String>()
+        );
+    }
+}
+
+impl Renderer for TerminalRenderer<'_> {
+    type Output = String;
+
+    fn render(&self, layout: &Layout) -> Self::Output {
+        let mut text = String::new();
+
+        push_line!(
+            text,
+            "{} manifest, {} Periods, {} to {}, {} per column",
+            if self.mpd.live.is_some() {
+                "Dynamic"
+            } else {
+                "Static"
+            },
+            self.mpd.periods.len(),
+            self.mpd.start(),
+            self.mpd.end(),
+            MediaTime::from_ms(self.ms_per_column().round() as u64)
+        );
+
+        self.write_time_axis(&mut text);
+
+        for (period_index, period) in self.mpd.periods.iter().enumerate() {
+            push_line!(
+                text,
+                "{}Period {} {} to {}",
+                style(self.color, "\x1b[1m"),
+                period.id,
+                period.start(),
+                period.end()
+            );
+            text.push_str(style(self.color, RESET));
+
+            for (adaptation_index, adaptation_set) in period.adaptation_sets.iter().enumerate() {
+                let mut header = format!(
+                    "  AdaptationSet {} {}",
+                    adaptation_set.id,
+                    adaptation_set.content_type.as_str()
+                );
+
+                if let Some(lang) = &adaptation_set.lang {
+                    header.push_str(&format!(" {}", lang));
+                }
+
+                push_line!(text, "{}", header);
+
+                for (representation_index, representation) in
+                    adaptation_set.representations.iter().enumerate()
+                {
+                    let key = element_key(&[period_index, adaptation_index, representation_index]);
+
+                    let cells = self.cells(
+                        layout,
+                        &key,
+                        adaptation_set.content_type,
+                        period.period_start,
+                    );
+
+                    push_fmt!(text, "{}", label(&representation.id));
+
+                    write_cells(&mut text, &cells, self.color);
+
+                    push_line!(text);
+                }
+            }
+        }
+
+        push_line!(
+            text,
+            "\n{}{} segments, alternating  {} gap  {} overlap",
+            paint(self.color, Cell::Segment(ContentType::Video, 0)),
+            paint(self.color, Cell::Segment(ContentType::Video, 1)),
+            paint(self.color, Cell::Gap),
+            paint(self.color, Cell::Overlap)
+        );
+
+        text
+    }
+}
+
+impl ExpandedMpd {
+    /// The manifest as text for a terminal `width` columns wide, laid out at a scale of a few
+    /// pixels per column so runs of segments shorter than a column collapse into one block
+    pub fn to_terminal(&self, width: usize, color: bool, options: &RenderOptions) -> String {
+        let renderer = TerminalRenderer {
+            mpd: self,
+            width,
+            color,
+        };
+
+        let scale = PIXELS_PER_COLUMN * 1000.0 / renderer.ms_per_column();
+
+        renderer.render(&self.layout(scale, options))
+    }
+}
+
+/// Runs of identical cells share a single escape sequence
+fn write_cells(text: &mut String, cells: &[Cell], color: bool) {
+    for run in cells.chunk_by(|a, b| a == b) {
+        let symbols: String = run.iter().map(|cell| cell.symbol(color)).collect();
+
+        match run[0].rgba() {
+            Some(rgba) if color => {
+                push_fmt!(text, "{}{}{}", foreground(rgba), symbols, RESET);
+            }
+            _ => text.push_str(&symbols),
+        }
+    }
+}
+
+fn paint(color: bool, cell: Cell) -> String {
+    let mut text = String::new();
+
+    write_cells(&mut text, &[cell], color);
+
+    text
+}
+
+fn foreground((r, g, b, _): (u8, u8, u8, u8)) -> String {
+    format!("\x1b[38;2;{};{};{}m", r, g, b)
+}
+
+fn style(color: bool, escape: &'static str) -> &'static str {
+    if color {
+        escape
+    } else {
+        ""
+    }
+}
+
+/// Indented and padded or cut to the label column
+fn label(id: &str) -> String {
+    let id: String = id.chars().take(LABEL_WIDTH - 5).collect();
+
+    format!("    {:<width$} ", id, width = LABEL_WIDTH - 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        expanded::{render::DEFAULT_MAX_HEIGHT, ParseMode},
+        util::fixture::manifest,
+    };
+
+    #[test]
+    fn rows_show_the_segments_gaps_and_overlaps_of_the_layout() {
+        // 0-2s, a gap to 3s, 3-4s overlapped by 3.5-6s
+        let mpd = manifest()
+            .adaptation_set_child(
+                r#"<SegmentTemplate timescale="1000" media="$Time$.m4s">
+                    <SegmentTimeline>
+                        <S t="0" d="2000"/>
+                        <S t="3000" d="1000"/>
+                        <S t="3500" d="2500"/>
+                    </SegmentTimeline>
+                </SegmentTemplate>"#,
+            )
+            .expand(ParseMode::Lenient)
+            .unwrap();
+
+        let options = RenderOptions {
+            debug: false,
+            time_labels: Default::default(),
+            pixels_per_second: None,
+            max_height: DEFAULT_MAX_HEIGHT,
+            drift_threshold: MediaTime::from_ms(100),
+        };
+
+        // 60 columns of 100ms
+        let text = mpd.to_terminal(LABEL_WIDTH + 1 + 60, false, &options);
+
+        assert!(text.starts_with("Static manifest, 1 Periods, 0.000s to 6.000s, 0.100s per column"));
+
+        let row = text
+            .lines()
+            .find(|line| line.starts_with("    v1"))
+            .unwrap();
+
+        assert_eq!(
+            row,
+            format!(
+                "{}{}{}{}{}{}",
+                label("v1"),
+                "█".repeat(20),
+                "░".repeat(10),
+                "▓".repeat(5),
+                "!".repeat(5),
+                "█".repeat(20)
+            )
+        );
+
+        assert_eq!(
+            text.lines().nth(1).unwrap().trim(),
+            "0s        1s        2s        3s        4s        5s"
+        );
+
+        // Half second ticks at 200 columns of 30ms
+        let text = mpd.to_terminal(LABEL_WIDTH + 1 + 200, false, &options);
+        let axis: Vec<&str> = text.lines().nth(1).unwrap().split_whitespace().collect();
+
+        assert_eq!(axis[..4], ["0s", "0.5s", "1s", "1.5s"]);
+    }
+}
@@ -0,0 +1,352 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Add, Mul},
+    time::Duration,
+};
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+/// An exact presentation time or duration, kept as ticks of a timescale (ticks per second) the
+/// way DASH expresses it. Converting to milliseconds only happens when rendering, so long
+/// timelines with 48kHz or 90kHz timescales do not accumulate rounding drift.
+#[derive(Debug, Clone, Copy)]
+pub struct MediaTime {
+    pub ticks: u64,
+    pub timescale: u64,
+}
+
+impl MediaTime {
+    pub const ZERO: MediaTime = MediaTime {
+        ticks: 0,
+        timescale: 1,
+    };
+
+    pub fn new(ticks: u64, timescale: u64) -> Self {
+        assert!(timescale > 0, "MediaTime with a timescale of 0");
+
+        MediaTime { ticks, timescale }
+    }
+
+    pub fn from_ms(ms: u64) -> Self {
+        MediaTime::new(ms, 1000)
+    }
+
+    /// Keeps millisecond precision where the duration allows it, nanoseconds otherwise
+    pub fn from_duration(duration: Duration) -> Option<Self> {
+        if duration.subsec_nanos().is_multiple_of(1_000_000) {
+            return duration.as_millis().try_into().ok().map(MediaTime::from_ms);
+        }
+
+        MediaTime::from_u128(duration.as_nanos(), 1_000_000_000)
+    }
+
+    /// Truncates to whole milliseconds, for display and drawing
+    pub fn as_ms(self) -> u64 {
+        (self.ticks as u128 * 1000 / self.timescale as u128) as u64
+    }
+
+    /// The number of whole ticks of `timescale` in this time, rounded down
+    pub fn to_timescale(self, timescale: u64) -> u64 {
+        (self.ticks as u128 * timescale as u128 / self.timescale as u128) as u64
+    }
+
+    pub fn saturating_sub(self, rhs: MediaTime) -> MediaTime {
+        let (lhs_ticks, rhs_ticks, timescale) = common_timescale(&self, &rhs);
+
+        MediaTime::from_u128_rounded(
+            lhs_ticks.saturating_sub(rhs_ticks),
+            timescale,
+            self.timescale.max(rhs.timescale),
+        )
+    }
+
+    /// How many times `rhs` fits in this time, rounded down
+    pub fn div_floor(self, rhs: MediaTime) -> u64 {
+        let (lhs_ticks, rhs_ticks, _) = common_timescale(&self, &rhs);
+
+        (lhs_ticks / rhs_ticks) as u64
+    }
+
+    /// How many times `rhs` is needed to cover this time, rounded up
+    pub fn div_ceil(self, rhs: MediaTime) -> u64 {
+        let (lhs_ticks, rhs_ticks, _) = common_timescale(&self, &rhs);
+
+        lhs_ticks.div_ceil(rhs_ticks) as u64
+    }
+
+    /// Falls back to the reduced fraction when the ticks do not fit the given timescale
+    fn from_u128(ticks: u128, timescale: u128) -> Option<Self> {
+        if let (Ok(ticks), Ok(timescale)) = (ticks.try_into(), timescale.try_into()) {
+            return Some(MediaTime::new(ticks, timescale));
+        }
+
+        let divisor = gcd(ticks, timescale);
+
+        Some(MediaTime::new(
+            (ticks / divisor).try_into().ok()?,
+            (timescale / divisor).try_into().ok()?,
+        ))
+    }
+
+    /// Rounds to the nearest tick of `fallback`, which divides `timescale`, when the exact time
+    /// does not fit. Saturates when neither does.
+    fn from_u128_rounded(ticks: u128, timescale: u128, fallback: u64) -> Self {
+        MediaTime::from_u128(ticks, timescale).unwrap_or_else(|| {
+            let divisor = timescale / fallback as u128;
+
+            MediaTime::new(
+                (ticks / divisor + u128::from(ticks % divisor >= divisor.div_ceil(2)))
+                    .try_into()
+                    .unwrap_or(u64::MAX),
+                fallback,
+            )
+        })
+    }
+}
+
+impl Add for MediaTime {
+    type Output = MediaTime;
+
+    fn add(self, rhs: MediaTime) -> MediaTime {
+        let (lhs_ticks, rhs_ticks, timescale) = common_timescale(&self, &rhs);
+
+        MediaTime::from_u128_rounded(
+            lhs_ticks.saturating_add(rhs_ticks),
+            timescale,
+            self.timescale.max(rhs.timescale),
+        )
+    }
+}
+
+impl Mul<u64> for MediaTime {
+    type Output = MediaTime;
+
+    fn mul(self, rhs: u64) -> MediaTime {
+        let ticks = self.ticks as u128 * rhs as u128;
+
+        MediaTime::from_u128_rounded(ticks, self.timescale as u128, self.timescale)
+    }
+}
+
+impl PartialEq for MediaTime {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MediaTime {}
+
+impl PartialOrd for MediaTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MediaTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.ticks as u128 * other.timescale as u128)
+            .cmp(&(other.ticks as u128 * self.timescale as u128))
+    }
+}
+
+/// A signed difference between two MediaTimes, as exact as they are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MediaOffset {
+    pub negative: bool,
+    pub magnitude: MediaTime,
+}
+
+impl MediaOffset {
+    /// `to` - `from`, negative when `to` is earlier
+    pub fn between(from: MediaTime, to: MediaTime) -> Self {
+        MediaOffset {
+            negative: to < from,
+            magnitude: to.max(from).saturating_sub(to.min(from)),
+        }
+    }
+}
+
+impl Add for MediaOffset {
+    type Output = MediaOffset;
+
+    fn add(self, rhs: MediaOffset) -> MediaOffset {
+        if self.negative == rhs.negative {
+            return MediaOffset {
+                negative: self.negative,
+                magnitude: self.magnitude + rhs.magnitude,
+            };
+        }
+
+        // Opposite signs, the larger magnitude wins
+        let (larger, smaller) = if self.magnitude >= rhs.magnitude {
+            (self, rhs)
+        } else {
+            (rhs, self)
+        };
+
+        let magnitude = larger.magnitude.saturating_sub(smaller.magnitude);
+
+        MediaOffset {
+            negative: larger.negative && magnitude > MediaTime::ZERO,
+            magnitude,
+        }
+    }
+}
+
+/// Signed seconds to the millisecond, e.g. +0.200s, -0.040s or 0.000s
+impl fmt::Display for MediaOffset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = match (self.magnitude.as_ms(), self.negative) {
+            (0, _) => "",
+            (_, true) => "-",
+            (_, false) => "+",
+        };
+
+        write!(f, "{}{}", sign, self.magnitude)
+    }
+}
+
+/// Like MediaTime, with signed ticks and seconds
+impl Serialize for MediaOffset {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let sign = if self.negative { -1 } else { 1 };
+
+        let mut state = serializer.serialize_struct("MediaOffset", 3)?;
+
+        state.serialize_field("ticks", &(sign * self.magnitude.ticks as i128))?;
+        state.serialize_field("timescale", &self.magnitude.timescale)?;
+        state.serialize_field(
+            "seconds",
+            &(sign as f64 * self.magnitude.ticks as f64 / self.magnitude.timescale as f64),
+        )?;
+
+        state.end()
+    }
+}
+
+/// Seconds to the millisecond, e.g. 1.920s
+impl fmt::Display for MediaTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.3}s", self.as_ms() as f64 / 1000.0)
+    }
+}
+
+/// Keeps the exact ticks alongside seconds, which is what most consumers want to plot
+impl Serialize for MediaTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("MediaTime", 3)?;
+
+        state.serialize_field("ticks", &self.ticks)?;
+        state.serialize_field("timescale", &self.timescale)?;
+        state.serialize_field("seconds", &(self.ticks as f64 / self.timescale as f64))?;
+
+        state.end()
+    }
+}
+
+/// Both times as ticks of the least common multiple of their timescales
+fn common_timescale(lhs: &MediaTime, rhs: &MediaTime) -> (u128, u128, u128) {
+    let lhs_timescale = lhs.timescale as u128;
+    let rhs_timescale = rhs.timescale as u128;
+
+    let timescale = lhs_timescale / gcd(lhs_timescale, rhs_timescale) * rhs_timescale;
+
+    (
+        lhs.ticks as u128 * (timescale / lhs_timescale),
+        rhs.ticks as u128 * (timescale / rhs_timescale),
+        timescale,
+    )
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a.max(1)
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_in_the_common_timescale() {
+        let sum = MediaTime::new(1, 3) + MediaTime::new(1, 2);
+
+        assert_eq!((sum.ticks, sum.timescale), (5, 6));
+
+        let sum = MediaTime::from_ms(1000) + MediaTime::new(48000, 48000);
+
+        assert_eq!((sum.ticks, sum.timescale), (96000, 48000));
+    }
+
+    #[test]
+    fn add_rounds_to_the_larger_timescale_on_overflow() {
+        // The common timescale of two primes would need more than 64 bits of ticks
+        let sum = MediaTime::new(u64::MAX / 2, 999_999_937) + MediaTime::new(1, 999_999_929);
+
+        assert_eq!(sum.timescale, 999_999_937);
+        assert_eq!(sum.ticks, u64::MAX / 2 + 1);
+    }
+
+    #[test]
+    fn mul_saturates_on_overflow() {
+        assert_eq!((MediaTime::new(u64::MAX, 7) * 2).ticks, u64::MAX);
+    }
+
+    #[test]
+    fn compare_across_timescales() {
+        assert_eq!(MediaTime::from_ms(1000), MediaTime::new(90000, 90000));
+        assert!(MediaTime::new(1, 3) < MediaTime::from_ms(334));
+        assert!(MediaTime::new(1, 3) > MediaTime::from_ms(333));
+        assert_eq!(
+            MediaTime::new(2, 3).max(MediaTime::from_ms(600)),
+            MediaTime::new(2, 3)
+        );
+    }
+
+    #[test]
+    fn from_duration() {
+        let time = MediaTime::from_duration(Duration::from_millis(1500)).unwrap();
+
+        assert_eq!((time.ticks, time.timescale), (1500, 1000));
+
+        let time = MediaTime::from_duration(Duration::from_nanos(1_000_000_001)).unwrap();
+
+        assert_eq!((time.ticks, time.timescale), (1_000_000_001, 1_000_000_000));
+    }
+
+    #[test]
+    fn display_in_seconds() {
+        assert_eq!(MediaTime::new(1, 3).to_string(), "0.333s");
+        assert_eq!(MediaTime::new(96000, 48000).to_string(), "2.000s");
+    }
+
+    #[test]
+    fn offset_sums_exactly() {
+        let late = MediaOffset::between(MediaTime::from_ms(6000), MediaTime::new(289_920, 48000));
+        let early = MediaOffset::between(MediaTime::new(1, 3), MediaTime::ZERO);
+
+        assert_eq!(late.to_string(), "+0.040s");
+        assert_eq!(early.to_string(), "-0.333s");
+
+        // 1/25 - 1/3 is -22/75
+        let sum = late + early;
+
+        assert!(sum.negative);
+        assert_eq!(sum.magnitude, MediaTime::new(22, 75));
+        assert_eq!(
+            sum + MediaOffset::between(MediaTime::ZERO, MediaTime::new(22, 75)),
+            MediaOffset::between(MediaTime::ZERO, MediaTime::ZERO)
+        );
+    }
+
+    #[test]
+    fn as_ms_truncates() {
+        assert_eq!(MediaTime::new(1, 3).as_ms(), 333);
+        assert_eq!(MediaTime::new(2, 3).as_ms(), 666);
+        assert_eq!(MediaTime::new(47999, 48000).as_ms(), 999);
+    }
+}
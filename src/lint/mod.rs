@@ -0,0 +1,213 @@
+mod report;
+mod rules;
+
+use std::{fmt, path::Path};
+
+use dash_mpd::MPD;
+use serde::Serialize;
+
+use crate::{
+    expanded::{ExpandedMpd, ParseMode},
+    push_line,
+    util::{
+        error::ParseError,
+        source::{find_element, scan_elements, SourceElement},
+    },
+};
+
+pub use report::{LintReport, ReportFormat};
+pub use rules::RULES;
+
+// Exit codes of `dmpd lint`, so CI can tell findings apart from input it could not check
+pub const EXIT_CLEAN: i32 = 0;
+pub const EXIT_WARNINGS: i32 = 1;
+pub const EXIT_ERRORS: i32 = 2;
+pub const EXIT_UNPARSEABLE: i32 = 3;
+
+/// How much a finding matters, following the SHALL and SHOULD of the DASH-IF IOP guidelines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A named check run over the parsed manifest and, when it expands, the expanded model
+pub struct Rule {
+    /// Findings are reported under this ID, e.g. segment-alignment
+    pub id: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+    check: fn(&MPD, Result<&ExpandedMpd, &ParseError>) -> Vec<Violation>,
+}
+
+/// Where a rule is broken and why, before the ID and severity of the rule are attached
+struct Violation {
+    path: ElementPath,
+    message: String,
+}
+
+/// Where a finding is in the manifest, from the MPD down. Displayed as e.g.
+/// /MPD/Period[@id="p0"]/AdaptationSet[2], elements without an @id are numbered from 1.
+#[derive(Debug, Clone, Default)]
+struct ElementPath {
+    /// Element name, @id and position among the siblings of that name
+    steps: Vec<(&'static str, Option<String>, usize)>,
+}
+
+impl ElementPath {
+    fn child(&self, name: &'static str, id: Option<&str>, index: usize) -> Self {
+        let mut steps = self.steps.clone();
+
+        steps.push((name, id.map(str::to_owned), index));
+
+        ElementPath { steps }
+    }
+
+    /// The line the element starts on in the manifest source. The MPD itself is not scanned.
+    fn line(&self, source: &[SourceElement]) -> Option<usize> {
+        let mut elements = source;
+        let mut line = None;
+
+        for (name, id, index) in self.steps.iter() {
+            let element = find_element(elements, name, id.as_deref().unwrap_or("No ID"), *index)?;
+
+            line = Some(element.start_line);
+            elements = &element.children;
+        }
+
+        line
+    }
+}
+
+impl fmt::Display for ElementPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "/MPD")?;
+
+        for (name, id, index) in self.steps.iter() {
+            match id {
+                Some(id) => write!(f, "/{}[@id=\"{}\"]", name, id)?,
+                None => write!(f, "/{}[{}]", name, index + 1)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    /// e.g. /MPD/Period[@id="p0"]/AdaptationSet[2], elements without an @id are numbered from 1
+    pub path: String,
+    /// Where the element starts in the manifest, counting from 1
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// Runs every rule of `RULES` over the manifest `xml`, errors first. The manifest is expanded in
+/// the default mode, rules over the expanded model are left out when that fails. Only a manifest
+/// that cannot be parsed at all is an error.
+pub fn lint(xml: &str, media_dir: Option<&Path>) -> Result<Vec<Finding>, ParseError> {
+    let mpd =
+        dash_mpd::parse(xml).map_err(|err| ParseError::CannotParseManifestFile(err.to_string()))?;
+
+    let source = scan_elements(xml);
+
+    let expanded = ExpandedMpd::new(mpd.clone(), &source, media_dir, ParseMode::Default);
+
+    let mut findings: Vec<Finding> = RULES
+        .iter()
+        .flat_map(|rule| {
+            (rule.check)(&mpd, expanded.as_ref())
+                .into_iter()
+                .map(|violation| Finding {
+                    rule: rule.id,
+                    severity: rule.severity,
+                    path: violation.path.to_string(),
+                    line: violation.path.line(&source),
+                    message: violation.message,
+                })
+        })
+        .collect();
+
+    // Stable, so findings of the same severity stay in catalogue order
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+
+    Ok(findings)
+}
+
+/// A line per rule of the catalogue
+pub fn describe_rules() -> String {
+    let mut text = String::new();
+
+    for rule in RULES.iter() {
+        push_line!(
+            text,
+            "{:<22}{:<9}{}",
+            rule.id,
+            rule.severity.as_str(),
+            rule.description
+        );
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fixture::manifest;
+
+    #[test]
+    fn unexpandable_manifest_is_a_finding() {
+        // The only S starts at 120s, past the live edge 60s in
+        let xml = manifest()
+            .dynamic("2024-01-01T00:00:00Z", "2024-01-01T00:01:00Z")
+            .adaptation_set_attribute("segmentAlignment", "true")
+            .adaptation_set_attribute("startWithSAP", "1")
+            .adaptation_set_child(
+                r#"<SegmentTemplate timescale="1000" media="$Time$.m4s">
+        <SegmentTimeline><S t="120000" d="2000" r="-1"/></SegmentTimeline>
+      </SegmentTemplate>"#,
+            )
+            .xml();
+
+        let findings = lint(&xml, None).unwrap();
+
+        let [finding] = &findings[..] else {
+            panic!("Expecting a single finding, got {:?}", findings);
+        };
+
+        assert_eq!(finding.rule, "expandable");
+        assert_eq!(finding.severity, Severity::Error);
+        assert!(finding.message.contains("SegmentTimelineWithoutSegments"));
+        assert_eq!(
+            finding.path,
+            r#"/MPD/Period[@id="p0"]/AdaptationSet[@id="1"]/Representation[@id="v1"]"#
+        );
+        assert_eq!(finding.line, Some(8));
+    }
+
+    #[test]
+    fn unparseable_manifest_is_an_error() {
+        let err = lint("<MPD><Period>", None).unwrap_err();
+
+        assert!(
+            matches!(err, ParseError::CannotParseManifestFile(ref source) if !source.is_empty())
+        );
+        assert!(err.describe().contains(
+            "ParseError::CannotParseManifestFile: Unable to parse the provided manifest. "
+        ));
+    }
+}
@@ -0,0 +1,356 @@
+use serde::Serialize;
+use serde_json::json;
+
+use super::{Finding, Severity, EXIT_CLEAN, EXIT_ERRORS, EXIT_WARNINGS, RULES};
+use crate::{push_fmt, push_line, util::xml::escape};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// How `dmpd lint` reports its findings
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ArgEnum)]
+pub enum ReportFormat {
+    /// A line per finding
+    #[default]
+    Text,
+    Json,
+    /// A test case per rule, failing when the rule has findings
+    Junit,
+    /// SARIF 2.1.0, e.g. for GitHub code scanning
+    Sarif,
+}
+
+/// Every finding for a single manifest
+#[derive(Debug, Serialize)]
+pub struct LintReport {
+    /// The manifest path as given
+    pub manifest: String,
+    pub errors: usize,
+    pub warnings: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl LintReport {
+    pub fn new(manifest: String, findings: Vec<Finding>) -> Self {
+        let count = |severity: Severity| {
+            findings
+                .iter()
+                .filter(|finding| finding.severity == severity)
+                .count()
+        };
+
+        LintReport {
+            manifest,
+            errors: count(Severity::Error),
+            warnings: count(Severity::Warning),
+            findings,
+        }
+    }
+
+    /// Errors take precedence over warnings, which only count above `max_warnings`
+    pub fn exit_code(&self, max_warnings: usize) -> i32 {
+        if self.errors > 0 {
+            EXIT_ERRORS
+        } else if self.warnings > max_warnings {
+            EXIT_WARNINGS
+        } else {
+            EXIT_CLEAN
+        }
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Text => self.to_text(),
+            ReportFormat::Json => to_json(self),
+            ReportFormat::Junit => self.to_junit(),
+            ReportFormat::Sarif => self.to_sarif(),
+        }
+    }
+
+    /// A line per finding under a count of each severity, e.g.
+    /// warning[segment-alignment] /MPD/Period[1]/AdaptationSet[1] (line 4): ...
+    fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        push_line!(
+            text,
+            "{}: {} errors, {} warnings",
+            self.manifest,
+            self.errors,
+            self.warnings
+        );
+
+        for finding in self.findings.iter() {
+            push_line!(
+                text,
+                "  {}[{}] {}: {}",
+                finding.severity.as_str(),
+                finding.rule,
+                describe_location(finding),
+                finding.message
+            );
+        }
+
+        text
+    }
+
+    /// A test suite for the manifest with a test case per rule of the catalogue. Every finding of
+    /// a rule is listed in the failure of its test case.
+    fn to_junit(&self) -> String {
+        let mut xml = String::new();
+
+        let failures = RULES
+            .iter()
+            .filter(|rule| self.findings.iter().any(|finding| finding.rule == rule.id))
+            .count();
+
+        let manifest = escape(&self.manifest);
+
+        push_line!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        push_line!(
+            xml,
+            r#"<testsuites name="dmpd lint" tests="{}" failures="{}">"#,
+            RULES.len(),
+            failures
+        );
+        push_line!(
+            xml,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" errors="0">"#,
+            manifest,
+            RULES.len(),
+            failures
+        );
+
+        for rule in RULES.iter() {
+            let findings: Vec<&Finding> = self
+                .findings
+                .iter()
+                .filter(|finding| finding.rule == rule.id)
+                .collect();
+
+            push_fmt!(
+                xml,
+                r#"    <testcase classname="{}" name="{}""#,
+                manifest,
+                rule.id
+            );
+
+            if findings.is_empty() {
+                push_line!(xml, "/>");
+                continue;
+            }
+
+            push_line!(
+                xml,
+                r#"><failure type="{}" message="{}">"#,
+                rule.severity.as_str(),
+                escape(rule.description)
+            );
+
+            for finding in findings {
+                push_line!(
+                    xml,
+                    "{}: {}",
+                    escape(&describe_location(finding)),
+                    escape(&finding.message)
+                );
+            }
+
+            push_line!(xml, "</failure></testcase>");
+        }
+
+        push_line!(xml, "  </testsuite>\n</testsuites>");
+
+        xml
+    }
+
+    /// A single run with the whole catalogue as the rules of the tool. Findings point at the line
+    /// their element starts on, with the element path as a logical location.
+    fn to_sarif(&self) -> String {
+        let rules: Vec<serde_json::Value> = RULES
+            .iter()
+            .map(|rule| {
+                json!({
+                    "id": rule.id,
+                    "shortDescription": { "text": rule.description },
+                    "defaultConfiguration": { "level": rule.severity.as_str() },
+                })
+            })
+            .collect();
+
+        let results: Vec<serde_json::Value> = self
+            .findings
+            .iter()
+            .map(|finding| {
+                let mut physical_location = json!({
+                    "artifactLocation": { "uri": self.manifest },
+                });
+
+                if let Some(line) = finding.line {
+                    physical_location["region"] = json!({ "startLine": line });
+                }
+
+                json!({
+                    "ruleId": finding.rule,
+                    "ruleIndex": RULES.iter().position(|rule| rule.id == finding.rule),
+                    "level": finding.severity.as_str(),
+                    "message": { "text": finding.message },
+                    "locations": [{
+                        "physicalLocation": physical_location,
+                        "logicalLocations": [{
+                            "fullyQualifiedName": finding.path,
+                            "kind": "element",
+                        }],
+                    }],
+                })
+            })
+            .collect();
+
+        to_json(&json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "dmpd",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    },
+                },
+                "artifacts": [{ "location": { "uri": self.manifest } }],
+                "results": results,
+            }],
+        }))
+    }
+}
+
+/// The element path, and the line it starts on where the element was found in the source
+fn describe_location(finding: &Finding) -> String {
+    match finding.line {
+        Some(line) => format!("{} (line {})", finding.path, line),
+        None => finding.path.clone(),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    // Only strings, numbers and derived structs are serialised, which cannot fail
+    let mut json = serde_json::to_string_pretty(value).expect("Lint report serialisation");
+
+    json.push('\n');
+
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(rule: &'static str, severity: Severity, line: Option<usize>) -> Finding {
+        Finding {
+            rule,
+            severity,
+            path: r#"/MPD/Period[@id="p0"]/AdaptationSet[1]"#.to_owned(),
+            line,
+            message: "Representations mix codecs avc1 (v1) & hvc1 (v2)".to_owned(),
+        }
+    }
+
+    fn report(findings: Vec<Finding>) -> LintReport {
+        LintReport::new("live <1>.mpd".to_owned(), findings)
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(report(vec![]).exit_code(0), EXIT_CLEAN);
+
+        let warnings = report(vec![
+            finding("consistent-codecs", Severity::Warning, Some(4)),
+            finding("segment-alignment", Severity::Warning, Some(4)),
+        ]);
+
+        assert_eq!(warnings.exit_code(0), EXIT_WARNINGS);
+        assert_eq!(warnings.exit_code(2), EXIT_CLEAN);
+
+        let errors = report(vec![
+            finding("expandable", Severity::Error, None),
+            finding("consistent-codecs", Severity::Warning, Some(4)),
+        ]);
+
+        assert_eq!(errors.exit_code(10), EXIT_ERRORS);
+    }
+
+    #[test]
+    fn junit_has_a_test_case_per_rule() {
+        let xml = report(vec![
+            finding("consistent-codecs", Severity::Warning, Some(4)),
+            finding("consistent-codecs", Severity::Warning, None),
+        ])
+        .render(ReportFormat::Junit);
+
+        assert!(xml.contains(&format!(
+            r#"<testsuites name="dmpd lint" tests="{}" failures="1">"#,
+            RULES.len()
+        )));
+        assert!(xml.contains(r#"<testsuite name="live &lt;1&gt;.mpd""#));
+        assert_eq!(xml.matches("<testcase ").count(), RULES.len());
+        assert_eq!(xml.matches("<failure ").count(), 1);
+        assert!(xml.contains(
+            r#"<testcase classname="live &lt;1&gt;.mpd" name="consistent-codecs"><failure type="warning""#
+        ));
+        assert!(xml.contains(r#"<testcase classname="live &lt;1&gt;.mpd" name="expandable"/>"#));
+
+        // Both findings in the one failure, escaped
+        assert!(xml.contains("(line 4): Representations mix codecs avc1 (v1) &amp; hvc1 (v2)"));
+        assert_eq!(xml.matches("Representations mix codecs").count(), 2);
+    }
+
+    #[test]
+    fn sarif_has_a_result_per_finding() {
+        let sarif = report(vec![
+            finding("expandable", Severity::Error, None),
+            finding("consistent-codecs", Severity::Warning, Some(4)),
+        ])
+        .render(ReportFormat::Sarif);
+
+        let sarif: serde_json::Value = serde_json::from_str(&sarif).unwrap();
+
+        assert_eq!(sarif["version"], "2.1.0");
+
+        let run = &sarif["runs"][0];
+
+        assert_eq!(run["tool"]["driver"]["name"], "dmpd");
+        assert_eq!(
+            run["tool"]["driver"]["rules"].as_array().unwrap().len(),
+            RULES.len()
+        );
+
+        let results = run["results"].as_array().unwrap();
+
+        assert_eq!(results.len(), 2);
+
+        let rule_index = RULES
+            .iter()
+            .position(|rule| rule.id == "consistent-codecs")
+            .unwrap();
+
+        assert_eq!(results[1]["ruleId"], "consistent-codecs");
+        assert_eq!(results[1]["ruleIndex"], rule_index);
+        assert_eq!(results[1]["level"], "warning");
+
+        let location = &results[1]["locations"][0];
+
+        assert_eq!(
+            location["physicalLocation"]["artifactLocation"]["uri"],
+            "live <1>.mpd"
+        );
+        assert_eq!(location["physicalLocation"]["region"]["startLine"], 4);
+        assert_eq!(
+            location["logicalLocations"][0]["fullyQualifiedName"],
+            r#"/MPD/Period[@id="p0"]/AdaptationSet[1]"#
+        );
+
+        // No region without a line
+        assert!(results[0]["locations"][0]["physicalLocation"]
+            .get("region")
+            .is_none());
+    }
+}
@@ -0,0 +1,566 @@
+use dash_mpd::{AdaptationSet, Period, Representation, MPD};
+
+use super::{ElementPath, Rule, Severity, Violation};
+use crate::{
+    expanded::{ContentType, ExpandedAdaptationSet, ExpandedMpd, ExpandedSegments, MediaTime},
+    util::{
+        error::{ManifestLocation, ParseError},
+        parse::parse_content_type,
+    },
+};
+
+/// The catalogue, in the order findings of the same severity are reported
+pub const RULES: [Rule; 7] = [
+    Rule {
+        id: "expandable",
+        severity: Severity::Error,
+        description: "The manifest expands into segments without errors",
+        check: expandable,
+    },
+    Rule {
+        id: "dynamic-period-id",
+        severity: Severity::Error,
+        description: "Every Period of a dynamic manifest has an @id",
+        check: dynamic_period_id,
+    },
+    Rule {
+        id: "max-segment-duration",
+        severity: Severity::Error,
+        description: "No segment is longer than MPD@maxSegmentDuration",
+        check: max_segment_duration,
+    },
+    Rule {
+        id: "segment-alignment",
+        severity: Severity::Warning,
+        description: "Audio and video AdaptationSets with more than one Representation have @segmentAlignment or @subsegmentAlignment",
+        check: segment_alignment,
+    },
+    Rule {
+        id: "start-with-sap",
+        severity: Severity::Warning,
+        description: "Audio and video Representations have a @startWithSAP of 1 or 2",
+        check: start_with_sap,
+    },
+    Rule {
+        id: "consistent-codecs",
+        severity: Severity::Warning,
+        description: "The Representations of an AdaptationSet share a codec family",
+        check: consistent_codecs,
+    },
+    Rule {
+        id: "timescale-mismatch",
+        severity: Severity::Warning,
+        description: "The Representations of an AdaptationSet share a timescale",
+        check: timescale_mismatch,
+    },
+];
+
+fn expandable(mpd: &MPD, expanded: Result<&ExpandedMpd, &ParseError>) -> Vec<Violation> {
+    let Err(err) = expanded else {
+        return vec![];
+    };
+
+    let path = match err {
+        ParseError::At { location, .. } => location_path(mpd, location),
+        _ => ElementPath::default(),
+    };
+
+    vec![Violation {
+        path,
+        message: err.describe().trim().to_owned(),
+    }]
+}
+
+fn dynamic_period_id(mpd: &MPD, _expanded: Result<&ExpandedMpd, &ParseError>) -> Vec<Violation> {
+    if mpd.mpdtype.as_deref() != Some("dynamic") {
+        return vec![];
+    }
+
+    mpd.periods
+        .iter()
+        .enumerate()
+        .filter(|(_, period)| period.id.is_none())
+        .map(|(index, period)| Violation {
+            path: period_path(index, period),
+            message: "No @id on a Period of a dynamic manifest, players cannot match it across MPD updates".to_owned(),
+        })
+        .collect()
+}
+
+fn max_segment_duration(mpd: &MPD, expanded: Result<&ExpandedMpd, &ParseError>) -> Vec<Violation> {
+    let (Ok(expanded), Some(max_segment_duration)) = (
+        expanded,
+        mpd.maxSegmentDuration.and_then(MediaTime::from_duration),
+    ) else {
+        return vec![];
+    };
+
+    let mut violations = vec![];
+
+    for (path, adaptation_set, expanded_adaptation_set) in expanded_adaptation_sets(mpd, expanded) {
+        for expanded_representation in expanded_adaptation_set.representations.iter() {
+            // Without a sidx a SegmentBase is a single span, not a segment
+            if let ExpandedSegments::SegmentBase { indexed: false, .. } =
+                expanded_representation.segments
+            {
+                continue;
+            }
+
+            let longest = expanded_representation
+                .segments
+                .segment_timeline()
+                .segments
+                .iter()
+                .map(|run| run.segment_duration)
+                .max();
+
+            let Some(longest) = longest.filter(|longest| *longest > max_segment_duration) else {
+                continue;
+            };
+
+            let Some(representation) = adaptation_set
+                .representations
+                .get(expanded_representation.source_index)
+            else {
+                continue;
+            };
+
+            violations.push(Violation {
+                path: representation_path(
+                    &path,
+                    expanded_representation.source_index,
+                    representation,
+                ),
+                message: format!(
+                    "Segments of up to {}, over MPD@maxSegmentDuration of {}",
+                    longest, max_segment_duration
+                ),
+            });
+        }
+    }
+
+    violations
+}
+
+fn segment_alignment(mpd: &MPD, _expanded: Result<&ExpandedMpd, &ParseError>) -> Vec<Violation> {
+    adaptation_sets(mpd)
+        .filter(|(_, _, adaptation_set)| {
+            is_audio_or_video(adaptation_set)
+                && adaptation_set.representations.len() > 1
+                && adaptation_set.segmentAlignment != Some(true)
+                && adaptation_set.subsegmentAlignment != Some(true)
+        })
+        .map(|(path, _, adaptation_set)| Violation {
+            path,
+            message: format!(
+                "No @segmentAlignment or @subsegmentAlignment across {} Representations, players cannot assume switching at segment boundaries is seamless",
+                adaptation_set.representations.len()
+            ),
+        })
+        .collect()
+}
+
+fn start_with_sap(mpd: &MPD, _expanded: Result<&ExpandedMpd, &ParseError>) -> Vec<Violation> {
+    let mut violations = vec![];
+
+    for (path, _, adaptation_set) in adaptation_sets(mpd) {
+        if !is_audio_or_video(adaptation_set) {
+            continue;
+        }
+
+        if let Some(start_with_sap) = adaptation_set.startWithSAP {
+            if !(1..=2).contains(&start_with_sap) {
+                violations.push(Violation {
+                    path: path.clone(),
+                    message: format!("@startWithSAP of {}, 1 or 2 is expected", start_with_sap),
+                });
+            }
+        }
+
+        let representations = || adaptation_set.representations.iter().enumerate();
+
+        // Reported once on the AdaptationSet when none of its Representations have one either
+        if adaptation_set.startWithSAP.is_none()
+            && representations().all(|(_, representation)| representation.startWithSAP.is_none())
+        {
+            violations.push(Violation {
+                path,
+                message: "No @startWithSAP on the AdaptationSet or its Representations, 1 or 2 is expected".to_owned(),
+            });
+
+            continue;
+        }
+
+        for (index, representation) in representations() {
+            let message = match representation.startWithSAP.or(adaptation_set.startWithSAP) {
+                // Inherited values were checked on the AdaptationSet
+                Some(start_with_sap)
+                    if representation.startWithSAP.is_some()
+                        && !(1..=2).contains(&start_with_sap) =>
+                {
+                    format!("@startWithSAP of {}, 1 or 2 is expected", start_with_sap)
+                }
+                None => "No @startWithSAP, 1 or 2 is expected".to_owned(),
+                _ => continue,
+            };
+
+            violations.push(Violation {
+                path: representation_path(&path, index, representation),
+                message,
+            });
+        }
+    }
+
+    violations
+}
+
+fn consistent_codecs(mpd: &MPD, _expanded: Result<&ExpandedMpd, &ParseError>) -> Vec<Violation> {
+    adaptation_sets(mpd)
+        .filter_map(|(path, _, adaptation_set)| {
+            let families = group_representations(adaptation_set, |_, representation| {
+                let codecs = representation
+                    .codecs
+                    .as_ref()
+                    .or(adaptation_set.codecs.as_ref())?;
+
+                // e.g. avc1 for avc1.64001f, mp4a for mp4a.40.2
+                Some(
+                    codecs
+                        .split(',')
+                        .map(|codec| codec.trim().split('.').next().unwrap_or_default())
+                        .collect::<Vec<&str>>()
+                        .join(","),
+                )
+            });
+
+            (families.len() > 1).then(|| Violation {
+                path,
+                message: format!("Representations mix codecs {}", describe_groups(&families)),
+            })
+        })
+        .collect()
+}
+
+fn timescale_mismatch(mpd: &MPD, expanded: Result<&ExpandedMpd, &ParseError>) -> Vec<Violation> {
+    let Ok(expanded) = expanded else {
+        return vec![];
+    };
+
+    expanded_adaptation_sets(mpd, expanded)
+        .filter_map(|(path, adaptation_set, expanded_adaptation_set)| {
+            // The timescale the segments were expanded in, inherited and defaulted
+            let timescales = group_representations(adaptation_set, |index, _| {
+                expanded_adaptation_set
+                    .representations
+                    .iter()
+                    .find(|representation| representation.source_index == index)?
+                    .segments
+                    .segment_timeline()
+                    .segments
+                    .first()
+                    .map(|run| run.segment_duration.timescale)
+            });
+
+            (timescales.len() > 1).then(|| Violation {
+                path,
+                message: format!(
+                    "Representations use timescales {}",
+                    describe_groups(&timescales)
+                ),
+            })
+        })
+        .collect()
+}
+
+/// Every AdaptationSet of the manifest with its path and Period
+fn adaptation_sets(mpd: &MPD) -> impl Iterator<Item = (ElementPath, &Period, &AdaptationSet)> {
+    mpd.periods
+        .iter()
+        .enumerate()
+        .flat_map(|(period_index, period)| {
+            let period_path = period_path(period_index, period);
+
+            period
+                .adaptations
+                .iter()
+                .enumerate()
+                .map(move |(index, adaptation_set)| {
+                    (
+                        adaptation_set_path(&period_path, index, adaptation_set),
+                        period,
+                        adaptation_set,
+                    )
+                })
+        })
+}
+
+/// Every AdaptationSet of the expanded model with its path and the AdaptationSet of the manifest
+/// it was expanded from
+fn expanded_adaptation_sets<'a>(
+    mpd: &'a MPD,
+    expanded: &'a ExpandedMpd,
+) -> impl Iterator<Item = (ElementPath, &'a AdaptationSet, &'a ExpandedAdaptationSet)> {
+    expanded.periods.iter().flat_map(move |expanded_period| {
+        let period = mpd.periods.get(expanded_period.source_index);
+
+        expanded_period
+            .adaptation_sets
+            .iter()
+            .filter_map(move |expanded_adaptation_set| {
+                let period = period?;
+                let index = expanded_adaptation_set.source_index;
+                let adaptation_set = period.adaptations.get(index)?;
+
+                Some((
+                    adaptation_set_path(
+                        &period_path(expanded_period.source_index, period),
+                        index,
+                        adaptation_set,
+                    ),
+                    adaptation_set,
+                    expanded_adaptation_set,
+                ))
+            })
+    })
+}
+
+fn is_audio_or_video(adaptation_set: &AdaptationSet) -> bool {
+    matches!(
+        parse_content_type(adaptation_set, &mut vec![]),
+        Ok(ContentType::Audio | ContentType::Video)
+    )
+}
+
+/// The Representations of an AdaptationSet by the value of `key`, in order of first appearance.
+/// Representations without a value are left out.
+fn group_representations<T: PartialEq>(
+    adaptation_set: &AdaptationSet,
+    key: impl Fn(usize, &Representation) -> Option<T>,
+) -> Vec<(T, Vec<String>)> {
+    let mut groups: Vec<(T, Vec<String>)> = vec![];
+
+    for (index, representation) in adaptation_set.representations.iter().enumerate() {
+        let Some(value) = key(index, representation) else {
+            continue;
+        };
+
+        let name = representation
+            .id
+            .clone()
+            .unwrap_or_else(|| format!("[{}]", index + 1));
+
+        match groups.iter_mut().find(|(group, _)| *group == value) {
+            Some((_, names)) => names.push(name),
+            None => groups.push((value, vec![name])),
+        }
+    }
+
+    groups
+}
+
+/// e.g. 90000 (v1, v2) and 12800 (v3)
+fn describe_groups<T: std::fmt::Display>(groups: &[(T, Vec<String>)]) -> String {
+    let groups: Vec<String> = groups
+        .iter()
+        .map(|(value, names)| format!("{} ({})", value, names.join(", ")))
+        .collect();
+
+    match groups.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+/// The path of the innermost element of `location` that is in the manifest
+fn location_path(mpd: &MPD, location: &ManifestLocation) -> ElementPath {
+    let Some((period_index, period)) = location
+        .period_index
+        .and_then(|index| Some((index, mpd.periods.get(index)?)))
+    else {
+        return ElementPath::default();
+    };
+
+    let path = period_path(period_index, period);
+
+    let Some((adaptation_index, adaptation_set)) = location
+        .adaptation_set_index
+        .and_then(|index| Some((index, period.adaptations.get(index)?)))
+    else {
+        return path;
+    };
+
+    let path = adaptation_set_path(&path, adaptation_index, adaptation_set);
+
+    match location
+        .representation_index
+        .and_then(|index| Some((index, adaptation_set.representations.get(index)?)))
+    {
+        Some((index, representation)) => representation_path(&path, index, representation),
+        None => path,
+    }
+}
+
+fn period_path(index: usize, period: &Period) -> ElementPath {
+    ElementPath::default().child("Period", period.id.as_deref(), index)
+}
+
+fn adaptation_set_path(
+    period_path: &ElementPath,
+    index: usize,
+    adaptation_set: &AdaptationSet,
+) -> ElementPath {
+    period_path.child("AdaptationSet", adaptation_set.id.as_deref(), index)
+}
+
+fn representation_path(
+    adaptation_set_path: &ElementPath,
+    index: usize,
+    representation: &Representation,
+) -> ElementPath {
+    adaptation_set_path.child("Representation", representation.id.as_deref(), index)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lint::lint,
+        util::fixture::{manifest, Manifest},
+    };
+
+    const TEMPLATE: &str =
+        r#"<SegmentTemplate timescale="1000" duration="2000" media="$Number$.m4s"/>"#;
+
+    /// A manifest breaking none of the rules, with two Representations
+    fn clean() -> Manifest {
+        manifest()
+            .adaptation_set_attribute("segmentAlignment", "true")
+            .adaptation_set_attribute("startWithSAP", "1")
+            .adaptation_set_child(TEMPLATE)
+            .representation("v2", &[("bandwidth", "2000000")])
+    }
+
+    /// The paths of the findings of the rule `id`
+    fn findings(id: &str, manifest: Manifest) -> Vec<String> {
+        lint(&manifest.xml(), None)
+            .unwrap()
+            .into_iter()
+            .filter(|finding| finding.rule == id)
+            .map(|finding| finding.path)
+            .collect()
+    }
+
+    #[test]
+    fn clean_manifest() {
+        assert!(lint(&clean().xml(), None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn expandable() {
+        let manifest = manifest()
+            .adaptation_set_attribute("segmentAlignment", "true")
+            .adaptation_set_attribute("startWithSAP", "1")
+            .adaptation_set_child(r#"<SegmentTemplate timescale="1000" duration="2000"/>"#);
+
+        assert_eq!(
+            findings("expandable", manifest),
+            [r#"/MPD/Period[@id="p0"]/AdaptationSet[@id="1"]/Representation[@id="v1"]"#]
+        );
+        assert!(findings("expandable", clean()).is_empty());
+    }
+
+    #[test]
+    fn dynamic_period_id() {
+        let live = || clean().dynamic("2024-01-01T00:00:00Z", "2024-01-01T00:01:00Z");
+
+        assert_eq!(
+            findings("dynamic-period-id", live().without_period_attribute("id")),
+            ["/MPD/Period[1]"]
+        );
+        assert!(findings("dynamic-period-id", live()).is_empty());
+    }
+
+    #[test]
+    fn max_segment_duration() {
+        assert_eq!(
+            findings(
+                "max-segment-duration",
+                clean().mpd_attribute("maxSegmentDuration", "PT1S")
+            ),
+            [
+                r#"/MPD/Period[@id="p0"]/AdaptationSet[@id="1"]/Representation[@id="v1"]"#,
+                r#"/MPD/Period[@id="p0"]/AdaptationSet[@id="1"]/Representation[@id="v2"]"#
+            ]
+        );
+        assert!(findings(
+            "max-segment-duration",
+            clean().mpd_attribute("maxSegmentDuration", "PT2S")
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn segment_alignment() {
+        let manifest = manifest()
+            .adaptation_set_attribute("startWithSAP", "1")
+            .adaptation_set_child(TEMPLATE)
+            .representation("v2", &[]);
+
+        assert_eq!(
+            findings("segment-alignment", manifest),
+            [r#"/MPD/Period[@id="p0"]/AdaptationSet[@id="1"]"#]
+        );
+        assert!(findings("segment-alignment", clean()).is_empty());
+    }
+
+    #[test]
+    fn start_with_sap() {
+        assert_eq!(
+            findings(
+                "start-with-sap",
+                clean().representation("v3", &[("startWithSAP", "3")])
+            ),
+            [r#"/MPD/Period[@id="p0"]/AdaptationSet[@id="1"]/Representation[@id="v3"]"#]
+        );
+        assert!(findings(
+            "start-with-sap",
+            clean().representation("v3", &[("startWithSAP", "2")])
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn consistent_codecs() {
+        assert_eq!(
+            findings(
+                "consistent-codecs",
+                clean().representation("v3", &[("codecs", "hvc1.1.6.L93.B0")])
+            ),
+            [r#"/MPD/Period[@id="p0"]/AdaptationSet[@id="1"]"#]
+        );
+        assert!(findings(
+            "consistent-codecs",
+            clean().representation("v3", &[("codecs", "avc1.640028")])
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn timescale_mismatch() {
+        let mismatch = clean().representation("v3", &[]).representation_child(
+            r#"<SegmentTemplate timescale="90000" duration="180000" media="$Number$.m4s"/>"#,
+        );
+
+        assert_eq!(
+            findings("timescale-mismatch", mismatch),
+            [r#"/MPD/Period[@id="p0"]/AdaptationSet[@id="1"]"#]
+        );
+
+        // 1000 inherited from the AdaptationSet and given again on the Representation
+        let same = clean().representation("v3", &[]).representation_child(
+            r#"<SegmentTemplate timescale="1000" duration="2000" media="$Number$.m4s"/>"#,
+        );
+
+        assert!(findings("timescale-mismatch", same).is_empty());
+    }
+}
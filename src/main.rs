@@ -1,45 +1,218 @@
-use std::{fs, path::PathBuf};
-
-use clap::Parser;
-use expanded::ExpandedMpd;
+use std::{
+    fs,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
+
+use clap::{ArgEnum, CommandFactory, Parser, Subcommand};
+use expanded::{
+    ExpandedMpd, MediaTime, ParseMode, RenderOptions, TimeLabels, DEFAULT_DRIFT_THRESHOLD_MS,
+    DEFAULT_MAX_HEIGHT,
+};
+use lint::{LintReport, ReportFormat};
 use util::har::extract_mpd;
 
 mod util {
     pub mod debug;
     pub mod error;
+    #[cfg(test)]
+    pub mod fixture;
     pub mod har;
     pub mod parse;
+    pub mod sidx;
+    pub mod source;
+    pub mod text;
     pub mod update;
+    pub mod xml;
 }
 
 mod expanded;
+mod lint;
 
-use crate::util::{debug, error::ParseError, update};
+use crate::util::{debug, error::ParseError, source::scan_elements, update};
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about, long_about = None)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     #[clap(value_parser, required = true)]
-    filename: String,
+    filename: Option<String>,
 
     #[clap(short, long, action)]
     debug: bool,
+
+    /// Output written next to each manifest
+    #[clap(short, long, arg_enum, value_parser, default_value = "png")]
+    format: OutputFormat,
+
+    /// Read sidx boxes from media files next to the manifest for SegmentBase Representations
+    #[clap(long, action)]
+    sidx: bool,
+
+    /// Fail on the first fallback taken while expanding the manifest
+    #[clap(long, action, conflicts_with = "lenient")]
+    strict: bool,
+
+    /// Skip the parts of the manifest that cannot be expanded and list every warning
+    #[clap(long, action)]
+    lenient: bool,
+
+    /// Vertical scale of the image. Picked automatically from --max-height when not provided
+    #[clap(long, value_parser = parse_pixels_per_second)]
+    pixels_per_second: Option<f64>,
+
+    /// Height the automatic scale fits the image within
+    #[clap(long, value_parser, default_value_t = DEFAULT_MAX_HEIGHT)]
+    max_height: u32,
+
+    /// What the time axis of image output is labelled with
+    #[clap(long, arg_enum, value_parser, default_value = "media")]
+    time_labels: TimeLabels,
+
+    /// Milliseconds audio and video can end apart at a Period boundary before it is flagged
+    #[clap(long, value_parser, default_value_t = DEFAULT_DRIFT_THRESHOLD_MS)]
+    drift_threshold: u64,
+
+    /// Columns of terminal output. Defaults to $COLUMNS, or 100
+    #[clap(long, value_parser)]
+    width: Option<usize>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Checks a manifest against the DASH-IF interoperability guidelines
+    Lint {
+        #[clap(value_parser, required_unless_present = "list-rules")]
+        filename: Option<String>,
+
+        /// Print the ID, severity and description of every rule instead
+        #[clap(long, action)]
+        list_rules: bool,
+
+        /// Read sidx boxes from media files next to the manifest for SegmentBase Representations
+        #[clap(long, action)]
+        sidx: bool,
+
+        /// How the findings are printed
+        #[clap(short, long, arg_enum, value_parser, default_value = "text")]
+        format: ReportFormat,
+
+        /// Warnings allowed before exiting with 1. Any error exits with 2.
+        #[clap(long, value_parser, default_value_t = 0)]
+        max_warnings: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ArgEnum)]
+enum OutputFormat {
+    Png,
+    Svg,
+    Markdown,
+    Json,
+    Html,
+    /// Printed rather than written to a file
+    Terminal,
+}
+
+impl OutputFormat {
+    /// Terminal output is printed, every other format is written to a file with this extension
+    fn extension(&self) -> Option<&'static str> {
+        match self {
+            OutputFormat::Png => Some("png"),
+            OutputFormat::Svg => Some("svg"),
+            OutputFormat::Markdown => Some("md"),
+            OutputFormat::Json => Some("json"),
+            OutputFormat::Html => Some("html"),
+            OutputFormat::Terminal => None,
+        }
+    }
+}
+
+impl Args {
+    fn mode(&self) -> ParseMode {
+        if self.strict {
+            ParseMode::Strict
+        } else if self.lenient {
+            ParseMode::Lenient
+        } else {
+            ParseMode::Default
+        }
+    }
+
+    fn terminal_width(&self) -> usize {
+        self.width
+            .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+            .unwrap_or(100)
+    }
+
+    fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            debug: self.debug,
+            time_labels: self.time_labels,
+            pixels_per_second: self.pixels_per_second,
+            max_height: self.max_height,
+            drift_threshold: self.drift_threshold(),
+        }
+    }
+
+    fn drift_threshold(&self) -> MediaTime {
+        MediaTime::from_ms(self.drift_threshold)
+    }
 }
 
 fn main() {
-    let args = Args::parse();
+    let args = Args::try_parse().unwrap_or_else(|err| {
+        // Invalid arguments to `dmpd lint` exit like unparseable input, clap's own 2 means errors
+        // were found there. Everything else exits the way clap does.
+        if !err.use_stderr() || !is_lint_invocation(std::env::args_os()) {
+            err.exit();
+        }
 
-    update::check_updates();
+        let _ = err.print();
+        std::process::exit(lint::EXIT_UNPARSEABLE);
+    });
 
     debug::DEBUG.store(args.debug, std::sync::atomic::Ordering::Relaxed);
 
-    let path = std::path::Path::new(&args.filename);
-
     debug!("Input: {:?}", args);
 
+    // Linting runs in CI, where nothing else can be printed alongside the report and the
+    // binary is not replaced, so updates are not checked
+    if let Some(Command::Lint {
+        filename,
+        list_rules,
+        sidx,
+        format,
+        max_warnings,
+    }) = &args.command
+    {
+        let code = match filename {
+            Some(filename) if !list_rules => {
+                lint_manifest(Path::new(filename), *sidx, *format, *max_warnings)
+            }
+            _ => {
+                print!("{}", lint::describe_rules());
+                lint::EXIT_CLEAN
+            }
+        };
+
+        std::process::exit(code);
+    }
+
+    update::check_updates();
+
+    // Required unless a subcommand is given
+    let path = Path::new(args.filename.as_deref().unwrap_or_default());
+
     if path.is_dir() {
         let file_names: Vec<PathBuf> = fs::read_dir(path)
-            .unwrap() // :')
+            .unwrap_or_else(|err| {
+                eprintln!("Unable to read directory {}: {}", path.display(), err);
+                std::process::exit(1);
+            })
             .filter_map(|entry| entry.ok())
             .map(|entry| entry.path())
             .filter_map(|entry| match entry.extension() {
@@ -54,129 +227,299 @@ fn main() {
             })
             .collect();
 
-        // Store all images in a png folder
-        let png_path = path.join("png");
+        // Store all output in a folder named after the format, e.g. png
+        let output_dir = match args.format.extension() {
+            Some(extension) => {
+                let output_dir = path.join(extension);
 
-        if !png_path.exists() {
-            debug!("Creating path {:?}", png_path);
-            let create_path_result = fs::create_dir(&png_path);
+                create_dir(&output_dir);
 
-            if create_path_result.is_err() {
-                panic!("Unable to create path {:?}", create_path_result.err())
+                output_dir
             }
-        }
+            None => path.to_path_buf(),
+        };
 
         for filename in file_names {
-            let xml = std::fs::read_to_string(&filename)
-                .expect(&ParseError::CannotOpenManifestFile.describe());
-
-            let mpd: dash_mpd::MPD =
-                dash_mpd::parse(&xml).expect(&ParseError::CannotParseManifestFile.describe());
-
-            let mut expanded = ExpandedMpd::new(mpd);
-
-            if let Some(image) = expanded.to_png(args.debug) {
-                let name: String = filename
-                    .file_stem()
-                    .expect("xx")
-                    .to_str()
-                    .expect("xx")
-                    .to_owned();
+            let (xml, expanded) = match read_manifest(&filename).and_then(|xml| {
+                expand_manifest(&xml, &filename, args.sidx.then_some(path), args.mode())
+                    .map(|expanded| (xml, expanded))
+            }) {
+                Ok(manifest) => manifest,
+                Err(err) => {
+                    eprintln!("Skipping {}: {}", filename.display(), err.describe());
+                    continue;
+                }
+            };
 
-                let output_path = png_path.join(format!("{}.png", name));
+            let name = filename
+                .file_stem()
+                .and_then(std::ffi::OsStr::to_str)
+                .unwrap_or("manifest");
 
-                image.save(output_path).unwrap();
+            if let Err(err) = write_output(&expanded, &xml, &output_dir.join(name), &args) {
+                eprintln!("{}", err);
             }
         }
     } else {
-        let extension = std::path::Path::new(&args.filename)
+        let extension = path
             .extension()
             .and_then(std::ffi::OsStr::to_str)
-            .expect(&ParseError::CannotReadFileExtension.describe());
+            .unwrap_or_else(|| exit_with(ParseError::CannotReadFileExtension));
 
-        let file_stem = std::path::Path::new(&args.filename)
+        let file_stem = path
             .file_stem()
             .and_then(std::ffi::OsStr::to_str)
-            .expect(&ParseError::CannotReadFileStem.describe());
+            .unwrap_or_else(|| exit_with(ParseError::CannotReadFileStem));
 
         match extension {
             "mpd" => {
-                let xml = std::fs::read_to_string(&args.filename)
-                    .expect(&ParseError::CannotOpenManifestFile.describe());
+                let xml = read_manifest(path).unwrap_or_else(|err| exit_with(err));
 
-                let mpd: dash_mpd::MPD =
-                    dash_mpd::parse(&xml).expect(&ParseError::CannotParseManifestFile.describe());
+                let expanded =
+                    expand_manifest(&xml, path, path.parent().filter(|_| args.sidx), args.mode())
+                        .unwrap_or_else(|err| exit_with(err));
 
-                let mut expanded = ExpandedMpd::new(mpd);
-
-                if let Some(image) = expanded.to_png(args.debug) {
-                    image.save(args.filename.replace(".mpd", ".png")).unwrap();
+                if let Err(err) = write_output(&expanded, &xml, path, &args) {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
                 }
             }
             "har" => {
-                let parent_path = std::path::Path::new(&args.filename)
-                    .parent()
-                    .expect(&format!(
-                        "Unable to read parent dir for input {}",
-                        &args.filename
-                    ));
+                let parent_path = path.parent().unwrap_or(Path::new(""));
 
                 let output_path = parent_path.join(file_stem);
 
-                if !output_path.exists() {
-                    let create_path_result = fs::create_dir(&output_path);
-
-                    if create_path_result.is_err() {
-                        panic!("Unable to create path {:?}", create_path_result.err())
-                    }
-                }
+                create_dir(&output_path);
 
-                let png_path = output_path.join("png");
+                let render_path = match args.format.extension() {
+                    Some(extension) => {
+                        let render_path = output_path.join(extension);
 
-                if !png_path.exists() {
-                    let create_path_result = fs::create_dir(&png_path);
+                        create_dir(&render_path);
 
-                    if create_path_result.is_err() {
-                        panic!("Unable to create path {:?}", create_path_result.err())
+                        render_path
                     }
-                }
+                    None => output_path.clone(),
+                };
 
                 let mpd_path = output_path.join("mpd");
 
-                if !mpd_path.exists() {
-                    let create_path_result = fs::create_dir(&mpd_path);
+                create_dir(&mpd_path);
+
+                let paths = extract_mpd(path, &mpd_path).unwrap_or_else(|err| exit_with(err));
 
-                    if create_path_result.is_err() {
-                        panic!("Unable to create path {:?}", create_path_result.err())
+                for path in paths {
+                    // Media files are never captured alongside a HAR, so sidx cannot be resolved
+                    let (xml, expanded) = match read_manifest(&path).and_then(|xml| {
+                        expand_manifest(&xml, &path, None, args.mode())
+                            .map(|expanded| (xml, expanded))
+                    }) {
+                        Ok(manifest) => manifest,
+                        Err(err) => {
+                            eprintln!("Skipping {}: {}", path.display(), err.describe());
+                            continue;
+                        }
+                    };
+
+                    let file_stem = path
+                        .file_stem()
+                        .and_then(std::ffi::OsStr::to_str)
+                        .unwrap_or("manifest");
+
+                    if let Err(err) =
+                        write_output(&expanded, &xml, &render_path.join(file_stem), &args)
+                    {
+                        eprintln!("{}", err);
                     }
                 }
+            }
+            _ => exit_with(ParseError::UnexpectedFileExtension),
+        }
+    }
+}
 
-                let paths = extract_mpd(&args.filename, &mpd_path);
+fn read_manifest(path: &Path) -> Result<String, ParseError> {
+    fs::read_to_string(path).map_err(|err| ParseError::CannotOpenManifestFile(err.to_string()))
+}
 
-                for path in paths {
-                    let path_str = path.to_str().expect("Unable to convert path to filename");
+/// Parses and expands the manifest read from `path`. In lenient mode the warnings are listed.
+fn expand_manifest(
+    xml: &str,
+    path: &Path,
+    media_dir: Option<&Path>,
+    mode: ParseMode,
+) -> Result<ExpandedMpd, ParseError> {
+    let mpd =
+        dash_mpd::parse(xml).map_err(|err| ParseError::CannotParseManifestFile(err.to_string()))?;
 
-                    let xml = std::fs::read_to_string(path_str)
-                        .expect(&ParseError::CannotOpenManifestFile.describe());
+    let expanded = ExpandedMpd::new(mpd, &scan_elements(xml), media_dir, mode)?;
 
-                    let mpd: dash_mpd::MPD = dash_mpd::parse(&xml)
-                        .expect(&ParseError::CannotParseManifestFile.describe());
+    if mode == ParseMode::Lenient && !expanded.warnings.is_empty() {
+        eprintln!("{}: {} warnings", path.display(), expanded.warnings.len());
 
-                    let mut expanded = ExpandedMpd::new(mpd);
+        for warning in expanded.warnings.iter() {
+            eprintln!("  {}", warning.describe().trim());
+        }
+    }
 
-                    if let Some(image) = expanded.to_png(args.debug) {
-                        let file_stem = path
-                            .file_stem()
-                            .and_then(std::ffi::OsStr::to_str)
-                            .expect(&format!("Unable to read file stem for path {:?}", path));
+    Ok(expanded)
+}
 
-                        image
-                            .save(png_path.join(format!("{}.png", file_stem)))
-                            .expect(&format!("Unable to save png file for path {:?}", path));
-                    }
-                }
-            }
-            _ => panic!("{}", &ParseError::UnexpectedFileExtension.describe()),
+/// Prints the lint report for the manifest at `path` and returns the exit code for it
+fn lint_manifest(path: &Path, sidx: bool, format: ReportFormat, max_warnings: usize) -> i32 {
+    let findings = match read_manifest(path)
+        .and_then(|xml| lint::lint(&xml, path.parent().filter(|_| sidx)))
+    {
+        Ok(findings) => findings,
+        Err(err) => {
+            eprintln!("{}", err.describe());
+            return lint::EXIT_UNPARSEABLE;
         }
+    };
+
+    let report = LintReport::new(path.display().to_string(), findings);
+
+    print!("{}", report.render(format));
+
+    report.exit_code(max_warnings)
+}
+
+/// Whether the command line runs `dmpd lint`, as routed by clap with errors ignored. Used where
+/// clap could not parse it. Options of the main command before `lint` conflict with the
+/// subcommand, clap then takes `lint` for the manifest, which cannot be a file named lint as the
+/// subcommand always wins.
+fn is_lint_invocation(args: impl IntoIterator<Item = std::ffi::OsString>) -> bool {
+    Args::command()
+        .ignore_errors(true)
+        .try_get_matches_from(args)
+        .is_ok_and(|matches| {
+            matches.subcommand_name() == Some("lint")
+                || matches
+                    .get_one::<String>("filename")
+                    .is_some_and(|filename| filename == "lint")
+        })
+}
+
+/// Renders `expanded` in the requested format to `path`, with the extension of that format.
+/// `xml` is the manifest it was expanded from.
+fn write_output(expanded: &ExpandedMpd, xml: &str, path: &Path, args: &Args) -> Result<(), String> {
+    let name = path
+        .file_stem()
+        .and_then(std::ffi::OsStr::to_str)
+        .unwrap_or("manifest");
+
+    let drift = expanded.describe_drift(args.drift_threshold());
+
+    let Some(extension) = args.format.extension() else {
+        let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+
+        println!("{}", name);
+        print!(
+            "{}",
+            expanded.to_terminal(args.terminal_width(), color, &args.render_options())
+        );
+
+        if !drift.is_empty() {
+            print!("\n{}", drift);
+        }
+
+        return Ok(());
+    };
+
+    // Every other format is written to a file, the drift report is printed alongside
+    if !drift.is_empty() {
+        print!("{}\n{}", name, drift);
+    }
+
+    let output_path = path.with_extension(extension);
+
+    let result = match args.format {
+        OutputFormat::Png => expanded
+            .to_png(&args.render_options())
+            .save(&output_path)
+            .map_err(|err| err.to_string()),
+        OutputFormat::Svg => fs::write(&output_path, expanded.to_svg(&args.render_options()))
+            .map_err(|err| err.to_string()),
+        OutputFormat::Markdown => {
+            fs::write(&output_path, expanded.to_markdown()).map_err(|err| err.to_string())
+        }
+        OutputFormat::Json => expanded
+            .to_json(args.drift_threshold())
+            .map_err(|err| err.to_string())
+            .and_then(|json| fs::write(&output_path, json).map_err(|err| err.to_string())),
+        OutputFormat::Html => fs::write(
+            &output_path,
+            expanded.to_html(xml, name, &args.render_options()),
+        )
+        .map_err(|err| err.to_string()),
+        OutputFormat::Terminal => unreachable!("Terminal output is printed"),
+    };
+
+    result.map_err(|err| format!("Unable to save {}: {}", output_path.display(), err))
+}
+
+fn parse_pixels_per_second(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(scale) if scale > 0.0 && scale.is_finite() => Ok(scale),
+        _ => Err(format!("{} is not a positive number", value)),
+    }
+}
+
+fn create_dir(path: &Path) {
+    if !path.exists() {
+        debug!("Creating path {:?}", path);
+
+        if let Err(err) = fs::create_dir(path) {
+            eprintln!("Unable to create path {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn exit_with(err: ParseError) -> ! {
+    eprintln!("{}", err.describe());
+    std::process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_lint(args: &[&str]) -> bool {
+        is_lint_invocation(args.iter().map(std::ffi::OsString::from))
+    }
+
+    #[test]
+    fn lint_invocation() {
+        assert!(is_lint(&["dmpd", "lint", "--format", "xml"]));
+        assert!(is_lint(&["dmpd", "--debug", "lint"]));
+        assert!(!is_lint(&["dmpd", "manifest.mpd", "--format", "xml"]));
+        assert!(!is_lint(&["dmpd", "--unknown"]));
+        assert!(is_lint(&[
+            "dmpd", "--format", "markdown", "lint", "long.mpd"
+        ]));
+        assert!(is_lint(&["dmpd", "lint", "--format", "bogus", "long.mpd"]));
+        assert!(is_lint(&["dmpd", "lint", "--unknown", "long.mpd"]));
+        assert!(!is_lint(&["dmpd", "--format", "lint", "long.mpd"]));
+    }
+
+    #[test]
+    fn unparseable_manifest_exit_code() {
+        let missing = Path::new("missing.mpd");
+
+        assert_eq!(
+            lint_manifest(missing, false, ReportFormat::Text, 0),
+            lint::EXIT_UNPARSEABLE
+        );
+
+        let path = std::env::temp_dir().join("dmpd-unparseable.mpd");
+
+        fs::write(&path, "<MPD><Period>").unwrap();
+
+        let code = lint_manifest(&path, false, ReportFormat::Text, 0);
+
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(code, lint::EXIT_UNPARSEABLE);
     }
 }
@@ -1,13 +1,76 @@
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::expanded::ContentType;
+
+/// Identifies where in a manifest an error occurred, by @id ("No ID" when there is none) and by
+/// position among the siblings
+#[derive(Debug, Clone, Default)]
+pub struct ManifestLocation {
+    pub period_id: Option<String>,
+    pub adaptation_set_id: Option<String>,
+    pub representation_id: Option<String>,
+    pub period_index: Option<usize>,
+    pub adaptation_set_index: Option<usize>,
+    pub representation_index: Option<usize>,
+}
+
+impl ManifestLocation {
+    pub fn period(id: &str, index: usize) -> Self {
+        ManifestLocation {
+            period_id: Some(id.to_owned()),
+            period_index: Some(index),
+            ..Default::default()
+        }
+    }
+
+    pub fn adaptation_set(&self, id: &str, index: usize) -> Self {
+        ManifestLocation {
+            adaptation_set_id: Some(id.to_owned()),
+            adaptation_set_index: Some(index),
+            representation_id: None,
+            representation_index: None,
+            ..self.clone()
+        }
+    }
+
+    pub fn representation(&self, id: &str, index: usize) -> Self {
+        ManifestLocation {
+            representation_id: Some(id.to_owned()),
+            representation_index: Some(index),
+            ..self.clone()
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        [
+            ("Period", &self.period_id),
+            ("AdaptationSet", &self.adaptation_set_id),
+            ("Representation", &self.representation_id),
+        ]
+        .iter()
+        .filter_map(|(element, id)| id.as_ref().map(|id| format!("{} {}", element, id)))
+        .collect::<Vec<String>>()
+        .join(" > ")
+    }
+}
+
 #[derive(Debug)]
 pub enum ParseError {
     CannotReadFileExtension,
     CannotReadFileStem,
     UnexpectedFileExtension,
-    CannotOpenManifestFile,
-    CannotParseManifestFile,
+    /// With the error reading the file
+    CannotOpenManifestFile(String),
+    /// With the error of the XML parser
+    CannotParseManifestFile(String),
+    CannotOpenHarFile,
+    CannotParseHarFile,
+    MpdWithoutPeriods,
+    PeriodWithoutAdaptationSets,
+    AdaptationSetWithoutRepresentations,
     CannotParsePeriodStartAsU64,
     CannotParsePeriodDurationAsU64,
-    // MpdWithoutAvailabilityStartTime,
+    MpdWithoutAvailabilityStartTime,
     AdaptationSetWithoutContentType,
     CannotInferRepresentationMimeType,
     CannotInferRepresentationCodecs,
@@ -16,36 +79,135 @@ pub enum ParseError {
     RepresentationWithoutWidth,
     RepresentationWithoutHeight,
     RepresentationWithoutBandwidth,
-    UnmappedRepresentationContentType,
     // SegmentTemplateWithoutTimescale,
+    SegmentTemplateWithoutMedia,
     // SegmentTemplateWithoutSegmentTimeline,
     SegmentTimelineWithoutSegments,
+    CannotResolveNegativeRepeat,
+    SegmentListWithoutSegments,
+    SegmentListWithoutDuration,
+    SegmentTemplateWithoutDuration,
+    SegmentTemplateWithoutSegments,
+    CannotInferSegmentTemplateEnd,
+    SegmentBaseWithoutDuration,
     // CannotInferSegmentTemplate,
     // CannotInferSegmentTemplateMediaUrl,
     // SegmentWithoutTime,
+    /// A warning raised while expanding in strict mode
+    StrictWarning(Box<ParseWarning>),
+    /// Any of the above, raised while expanding the given part of the manifest
+    At {
+        location: Box<ManifestLocation>,
+        error: Box<ParseError>,
+    },
 }
 
 impl ParseError {
+    /// Attaches the location the error was raised at. The innermost location is kept.
+    pub fn at(self, location: &ManifestLocation) -> Self {
+        match self {
+            ParseError::At { .. } => self,
+            _ => ParseError::At {
+                location: Box::new(location.clone()),
+                error: Box::new(self),
+            },
+        }
+    }
+
+    /// The manifest attribute responsible for the error, where there is a single one
+    pub fn attribute(&self) -> Option<&'static str> {
+        match self {
+            ParseError::CannotParsePeriodStartAsU64 => Some("Period@start"),
+            ParseError::CannotParsePeriodDurationAsU64 => Some("Period@duration"),
+            ParseError::MpdWithoutAvailabilityStartTime => Some("MPD@availabilityStartTime"),
+            ParseError::CannotInferRepresentationMimeType => Some("@mimeType"),
+            ParseError::CannotInferRepresentationCodecs => Some("@codecs"),
+            ParseError::CannotInferRepresentationAudioSamplingRate => Some("@audioSamplingRate"),
+            ParseError::CannotInferRepresentationFrameRate => Some("@frameRate"),
+            ParseError::RepresentationWithoutWidth => Some("@width"),
+            ParseError::RepresentationWithoutHeight => Some("@height"),
+            ParseError::RepresentationWithoutBandwidth => Some("@bandwidth"),
+            ParseError::SegmentTemplateWithoutMedia => Some("SegmentTemplate@media"),
+            ParseError::SegmentTemplateWithoutDuration => Some("SegmentTemplate@duration"),
+            ParseError::SegmentListWithoutDuration => Some("SegmentList@duration"),
+            ParseError::SegmentBaseWithoutDuration => Some("SegmentBase@presentationDuration"),
+            ParseError::CannotResolveNegativeRepeat => Some("S@r"),
+            ParseError::At { error, .. } => error.attribute(),
+            _ => None,
+        }
+    }
+
     pub fn describe(&self) -> String {
-        let description = match self {
+        if let ParseError::At { location, error } = self {
+            let attribute = match error.attribute() {
+                Some(attribute) => format!(" ({})", attribute),
+                None => "".to_owned(),
+            };
+
+            return format!(
+                "\nParseError::{:?} at {}{}: {}\n",
+                error,
+                location.describe(),
+                attribute,
+                error.description()
+            );
+        }
+
+        match self {
+            ParseError::CannotOpenManifestFile(source) => {
+                return format!(
+                    "\nParseError::CannotOpenManifestFile: {} {}\n",
+                    self.description(),
+                    source
+                );
+            }
+            ParseError::CannotParseManifestFile(source) => {
+                return format!(
+                    "\nParseError::CannotParseManifestFile: {} {}\n",
+                    self.description(),
+                    source
+                );
+            }
+            _ => {}
+        }
+
+        if let ParseError::StrictWarning(warning) = self {
+            return format!(
+                "\nParseError::StrictWarning: {}",
+                warning.describe().trim_start()
+            );
+        }
+
+        format!("\nParseError::{:?}: {}\n", self, self.description())
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
             ParseError::CannotReadFileStem => "Unable to read the input file stem",
             ParseError::CannotReadFileExtension => "Unable to read the provided file's extension.",
             ParseError::UnexpectedFileExtension => {
                 "Unsupported file extension. Provide a har or mpd file"
             }
-            ParseError::CannotOpenManifestFile => "Unable to open the provided manifest.",
+            ParseError::CannotOpenManifestFile(_) => "Unable to open the provided manifest.",
             ParseError::CannotParsePeriodStartAsU64 => {
                 "Unable to parse period start in ms when casting to u64."
             }
             ParseError::CannotParsePeriodDurationAsU64 => {
                 "Unable to parse period duration in ms when casting to u64."
             }
-            ParseError::CannotParseManifestFile => "Unable to parse the provided manifest.",
-            // ParseError::MpdWithoutAvailabilityStartTime => {
-            //     "No availabilityStartTime on manifest. VOD manifests not yet supported."
-            // }
+            ParseError::CannotParseManifestFile(_) => "Unable to parse the provided manifest.",
+            ParseError::CannotOpenHarFile => "Unable to open the provided HAR file.",
+            ParseError::CannotParseHarFile => "Unable to parse the provided HAR file.",
+            ParseError::MpdWithoutPeriods => "No Period elements in the manifest",
+            ParseError::PeriodWithoutAdaptationSets => "No AdaptationSet elements in a Period",
+            ParseError::AdaptationSetWithoutRepresentations => {
+                "No Representation elements in an AdaptationSet"
+            }
+            ParseError::MpdWithoutAvailabilityStartTime => {
+                "No availabilityStartTime on a dynamic manifest. Live timing cannot be mapped."
+            }
             ParseError::AdaptationSetWithoutContentType => {
-                "Found AdaptationSetWithoutContentType without or with an unexpected contentType"
+                "Unable to determine an AdaptationSet's content type. Checked @contentType, then @mimeType, @codecs, @width, @height and @audioSamplingRate on the AdaptationSet and its Representations"
             }
             ParseError::CannotInferRepresentationMimeType => {
                 "Unable to infer the mimeType for a Representation"
@@ -62,10 +224,8 @@ impl ParseError {
             ParseError::RepresentationWithoutWidth => "No width on Representation",
             ParseError::RepresentationWithoutHeight => "No height on Representation",
             ParseError::RepresentationWithoutBandwidth => "No bandwidth on Representation",
-            ParseError::UnmappedRepresentationContentType => {
-                "A contentType has no description mapping"
-            }
             // ParseError::SegmentTemplateWithoutTimescale => "No timescale on SegmentTemplate",
+            ParseError::SegmentTemplateWithoutMedia => "No media on SegmentTemplate",
             // ParseError::SegmentTemplateWithoutSegmentTimeline => {
             //     "No SegmentTimeline within a SegmentTemplate. SegmentList is not supported."
             // }
@@ -79,11 +239,173 @@ impl ParseError {
             //     "No t attribute on <S> segment with $Time$ based media URL"
             // }
             ParseError::SegmentTimelineWithoutSegments => {
-                "Unable to get first segment from a SegmentTimeline"
+                "No segments in a SegmentTimeline. It has no S elements, or every S resolves to none, e.g. a negative S@r starting past the live edge"
+            }
+            ParseError::SegmentListWithoutSegments => "No SegmentURL elements in a SegmentList",
+            ParseError::SegmentListWithoutDuration => {
+                "SegmentList has neither a @duration nor a SegmentTimeline"
+            }
+            ParseError::SegmentTemplateWithoutDuration => {
+                "SegmentTemplate has neither a @duration nor a SegmentTimeline"
+            }
+            ParseError::SegmentBaseWithoutDuration => {
+                "Unable to infer the duration of a SegmentBase. No @presentationDuration or Period end"
+            }
+            ParseError::SegmentTemplateWithoutSegments => {
+                "No segments for a @duration SegmentTemplate. The Period has no duration, or ends before the live availability window starts"
+            }
+            ParseError::CannotResolveNegativeRepeat => {
+                "Unable to resolve a negative S@r. No following S@t, Period end, mediaPresentationDuration or live edge"
             }
+            ParseError::CannotInferSegmentTemplateEnd => {
+                "Unable to infer where a @duration SegmentTemplate ends. No Period@duration, following Period@start, mediaPresentationDuration or live availability window"
+            }
+            ParseError::StrictWarning(_) => "Warning raised in strict mode",
+            ParseError::At { error, .. } => error.description(),
+        }
+    }
+}
+
+/// A fallback taken while expanding a manifest. Expansion carries on, but the output may not
+/// reflect what a player would do.
+#[derive(Debug)]
+pub enum ParseWarning {
+    PeriodWithoutId,
+    RepresentationWithoutId,
+    PeriodStartInferredFromPreviousPeriod,
+    /// No usable @contentType, `content_type` was inferred from the attribute and value in `from`,
+    /// e.g. Representation@mimeType audio/mp4
+    ContentTypeInferred {
+        content_type: ContentType,
+        from: String,
+    },
+    DefaultedPresentationTimeOffset,
+    DefaultedTimescale,
+    SegmentTemplateInheritedFromAdaptationSet,
+    SegmentTemplateInheritedFromPeriod,
+    SegmentListInheritedFromAdaptationSet,
+    SegmentListInheritedFromPeriod,
+    SegmentBaseInheritedFromAdaptationSet,
+    SegmentBaseInheritedFromPeriod,
+    SidxNotRead,
+    /// Representation attributes such as @frameRate that are only used to describe it are missing,
+    /// it is described from what is known instead
+    IncompleteRepresentationDescription(Box<ParseError>),
+    /// An element that failed to expand and was left out in lenient mode
+    Skipped(Box<ParseError>),
+    /// Any of the above, raised while expanding the given part of the manifest
+    At {
+        location: ManifestLocation,
+        warning: Box<ParseWarning>,
+    },
+}
+
+impl ParseWarning {
+    /// Attaches the location the warning was raised at. The innermost location is kept.
+    pub fn at(self, location: &ManifestLocation) -> Self {
+        match self {
+            ParseWarning::At { .. } => self,
+            _ => ParseWarning::At {
+                location: location.clone(),
+                warning: Box::new(self),
+            },
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ParseWarning::At { location, warning } => format!(
+                "\nParseWarning::{} at {}: {}\n",
+                warning.name(),
+                location.describe(),
+                warning.description()
+            ),
+            _ => format!("\nParseWarning::{}: {}\n", self.name(), self.description()),
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            ParseWarning::Skipped(_) => "Skipped".to_owned(),
+            ParseWarning::IncompleteRepresentationDescription(_) => {
+                "IncompleteRepresentationDescription".to_owned()
+            }
+            ParseWarning::ContentTypeInferred { .. } => "ContentTypeInferred".to_owned(),
+            ParseWarning::At { warning, .. } => warning.name(),
+            _ => format!("{:?}", self),
+        }
+    }
+
+    fn description(&self) -> String {
+        let description = match self {
+            ParseWarning::PeriodWithoutId => "No @id on Period, using \"No ID\"",
+            ParseWarning::RepresentationWithoutId => "No @id on Representation, using \"No ID\"",
+            ParseWarning::PeriodStartInferredFromPreviousPeriod => {
+                "No Period@start, using the end of the previous Period"
+            }
+            ParseWarning::ContentTypeInferred { content_type, from } => {
+                return format!(
+                    "No @contentType, {} inferred from {}",
+                    content_type.as_str(),
+                    from
+                );
+            }
+            ParseWarning::DefaultedPresentationTimeOffset => {
+                "No @presentationTimeOffset, defaulting to 0"
+            }
+            ParseWarning::DefaultedTimescale => "No @timescale, defaulting to 1",
+            ParseWarning::SegmentTemplateInheritedFromAdaptationSet => {
+                "No SegmentTemplate on Representation, inherited from the AdaptationSet"
+            }
+            ParseWarning::SegmentTemplateInheritedFromPeriod => {
+                "No SegmentTemplate on Representation or AdaptationSet, inherited from the Period"
+            }
+            ParseWarning::SegmentListInheritedFromAdaptationSet => {
+                "No SegmentList on Representation, inherited from the AdaptationSet"
+            }
+            ParseWarning::SegmentListInheritedFromPeriod => {
+                "No SegmentList on Representation or AdaptationSet, inherited from the Period"
+            }
+            ParseWarning::SegmentBaseInheritedFromAdaptationSet => {
+                "No SegmentBase on Representation, inherited from the AdaptationSet"
+            }
+            ParseWarning::SegmentBaseInheritedFromPeriod => {
+                "No SegmentBase on Representation or AdaptationSet, inherited from the Period"
+            }
+            ParseWarning::SidxNotRead => {
+                "Unable to read the sidx from a local media file, drawing a single span"
+            }
+            ParseWarning::IncompleteRepresentationDescription(error) => {
+                return format!(
+                    "Describing the Representation from what is known. {}",
+                    error.describe().trim()
+                );
+            }
+            ParseWarning::Skipped(error) => {
+                return format!("Left out. {}", error.describe().trim());
+            }
+            ParseWarning::At { warning, .. } => return warning.description(),
+        };
+
+        description.to_owned()
+    }
+}
+
+/// Serialised flat, with the location as its description, e.g. "Period 1 > AdaptationSet 2"
+impl Serialize for ParseWarning {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let location = match self {
+            ParseWarning::At { location, .. } => Some(location.describe()),
+            _ => None,
         };
 
-        format!("\nParseError::{:?}: {}\n", self, description)
+        let mut state = serializer.serialize_struct("ParseWarning", 3)?;
+
+        state.serialize_field("warning", &self.name())?;
+        state.serialize_field("location", &location)?;
+        state.serialize_field("description", &self.description())?;
+
+        state.end()
     }
 }
 
@@ -0,0 +1,264 @@
+//! Manifests for tests, built up from a static 6s manifest with a single video Representation
+
+use crate::{
+    expanded::{ExpandedMpd, ParseMode},
+    push_fmt, push_line,
+    util::{error::ParseError, source::scan_elements},
+};
+
+/// An element with its attributes in order and its children as XML
+#[derive(Clone)]
+struct Element {
+    attributes: Vec<(String, String)>,
+    children: Vec<String>,
+}
+
+impl Element {
+    fn new(attributes: &[(&str, &str)]) -> Self {
+        Element {
+            attributes: attributes
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            children: vec![],
+        }
+    }
+
+    /// Replaces the attribute when present, adds it otherwise
+    fn set(&mut self, name: &str, value: &str) {
+        match self.attributes.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_owned(),
+            None => self.attributes.push((name.to_owned(), value.to_owned())),
+        }
+    }
+
+    fn remove(&mut self, name: &str) {
+        self.attributes.retain(|(n, _)| n != name);
+    }
+
+    fn start_tag(&self, name: &str) -> String {
+        let mut tag = format!("<{}", name);
+
+        for (attribute, value) in self.attributes.iter() {
+            push_fmt!(tag, r#" {}="{}""#, attribute, value);
+        }
+
+        tag.push('>');
+
+        tag
+    }
+}
+
+struct AdaptationSet {
+    element: Element,
+    representations: Vec<Element>,
+}
+
+struct Period {
+    element: Element,
+    adaptation_sets: Vec<AdaptationSet>,
+}
+
+/// A manifest under construction. Attributes and children are added to the last Period,
+/// AdaptationSet or Representation.
+pub struct Manifest {
+    mpd: Element,
+    periods: Vec<Period>,
+}
+
+/// A static manifest with Period p0 from 0s, video AdaptationSet 1 and Representation v1, and no
+/// segment information
+pub fn manifest() -> Manifest {
+    Manifest {
+        mpd: Element::new(&[
+            ("xmlns", "urn:mpeg:dash:schema:mpd:2011"),
+            ("type", "static"),
+            ("mediaPresentationDuration", "PT6S"),
+            ("minBufferTime", "PT2S"),
+            ("profiles", "urn:mpeg:dash:profile:isoff-main:2011"),
+        ]),
+        periods: vec![],
+    }
+    .period("p0", "PT0S")
+}
+
+/// A video AdaptationSet with a single Representation
+fn video_adaptation_set(id: &str) -> AdaptationSet {
+    AdaptationSet {
+        element: Element::new(&[
+            ("id", id),
+            ("contentType", "video"),
+            ("mimeType", "video/mp4"),
+            ("codecs", "avc1.64001f"),
+            ("frameRate", "25"),
+        ]),
+        representations: vec![video_representation("v1")],
+    }
+}
+
+fn video_representation(id: &str) -> Element {
+    Element::new(&[
+        ("id", id),
+        ("bandwidth", "1000000"),
+        ("width", "1280"),
+        ("height", "720"),
+    ])
+}
+
+impl Manifest {
+    /// A dynamic manifest whose live edge is `publish_time` - `availability_start_time`
+    pub fn dynamic(mut self, availability_start_time: &str, publish_time: &str) -> Self {
+        self.mpd.set("type", "dynamic");
+        self.mpd.remove("mediaPresentationDuration");
+        self.mpd
+            .set("availabilityStartTime", availability_start_time);
+        self.mpd.set("publishTime", publish_time);
+        self.mpd
+            .set("profiles", "urn:mpeg:dash:profile:isoff-live:2011");
+
+        self
+    }
+
+    pub fn mpd_attribute(mut self, name: &str, value: &str) -> Self {
+        self.mpd.set(name, value);
+        self
+    }
+
+    /// Adds a Period with a video AdaptationSet and Representation, `start` is left out when empty
+    pub fn period(mut self, id: &str, start: &str) -> Self {
+        let mut element = Element::new(&[("id", id)]);
+
+        if !start.is_empty() {
+            element.set("start", start);
+        }
+
+        self.periods.push(Period {
+            element,
+            adaptation_sets: vec![video_adaptation_set("1")],
+        });
+
+        self
+    }
+
+    pub fn period_attribute(mut self, name: &str, value: &str) -> Self {
+        self.last_period().element.set(name, value);
+        self
+    }
+
+    pub fn without_period_attribute(mut self, name: &str) -> Self {
+        self.last_period().element.remove(name);
+        self
+    }
+
+    pub fn period_child(mut self, xml: &str) -> Self {
+        self.last_period().element.children.push(xml.to_owned());
+        self
+    }
+
+    pub fn adaptation_set_attribute(mut self, name: &str, value: &str) -> Self {
+        self.last_adaptation_set().element.set(name, value);
+        self
+    }
+
+    pub fn without_adaptation_set_attribute(mut self, name: &str) -> Self {
+        self.last_adaptation_set().element.remove(name);
+        self
+    }
+
+    pub fn adaptation_set_child(mut self, xml: &str) -> Self {
+        self.last_adaptation_set()
+            .element
+            .children
+            .push(xml.to_owned());
+        self
+    }
+
+    /// Adds a video Representation to the last AdaptationSet, with `attributes` added or replaced
+    pub fn representation(mut self, id: &str, attributes: &[(&str, &str)]) -> Self {
+        let mut representation = video_representation(id);
+
+        for (name, value) in attributes {
+            representation.set(name, value);
+        }
+
+        self.last_adaptation_set()
+            .representations
+            .push(representation);
+        self
+    }
+
+    pub fn representation_child(mut self, xml: &str) -> Self {
+        self.last_representation().children.push(xml.to_owned());
+        self
+    }
+
+    /// The manifest, an element per line
+    pub fn xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\"?>\n");
+
+        push_line!(xml, "{}", self.mpd.start_tag("MPD"));
+
+        for period in self.periods.iter() {
+            push_line!(xml, "  {}", period.element.start_tag("Period"));
+
+            for child in period.element.children.iter() {
+                push_line!(xml, "    {}", child);
+            }
+
+            for adaptation_set in period.adaptation_sets.iter() {
+                push_line!(
+                    xml,
+                    "    {}",
+                    adaptation_set.element.start_tag("AdaptationSet")
+                );
+
+                for child in adaptation_set.element.children.iter() {
+                    push_line!(xml, "      {}", child);
+                }
+
+                for representation in adaptation_set.representations.iter() {
+                    push_line!(xml, "      {}", representation.start_tag("Representation"));
+
+                    for child in representation.children.iter() {
+                        push_line!(xml, "        {}", child);
+                    }
+
+                    push_line!(xml, "      </Representation>");
+                }
+
+                push_line!(xml, "    </AdaptationSet>");
+            }
+
+            push_line!(xml, "  </Period>");
+        }
+
+        xml.push_str("</MPD>\n");
+
+        xml
+    }
+
+    pub fn expand(&self, mode: ParseMode) -> Result<ExpandedMpd, ParseError> {
+        let xml = self.xml();
+        let mpd = dash_mpd::parse(&xml).expect("Unable to parse the test manifest");
+
+        ExpandedMpd::new(mpd, &scan_elements(&xml), None, mode)
+    }
+
+    fn last_period(&mut self) -> &mut Period {
+        self.periods.last_mut().expect("No Period")
+    }
+
+    fn last_adaptation_set(&mut self) -> &mut AdaptationSet {
+        self.last_period()
+            .adaptation_sets
+            .last_mut()
+            .expect("No AdaptationSet")
+    }
+
+    fn last_representation(&mut self) -> &mut Element {
+        self.last_adaptation_set()
+            .representations
+            .last_mut()
+            .expect("No Representation")
+    }
+}
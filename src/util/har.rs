@@ -1,11 +1,13 @@
 use std::fs::File;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use chrono::DateTime;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+use crate::{debug, util::error::ParseError};
+
 #[derive(Serialize, Deserialize, Debug)]
 struct Har {
     log: Log,
@@ -48,15 +50,21 @@ struct Content {
     encoding: Option<String>,
 }
 
-pub fn extract_mpd(har_path_string: &String, output_dir_path: &PathBuf) -> Vec<PathBuf> {
-    let path = std::path::Path::new(har_path_string);
-
+/// Writes every dash+xml response in the HAR file to `output_dir_path`, returning the paths
+/// written. Entries that cannot be saved are reported and skipped.
+pub fn extract_mpd(har_path: &Path, output_dir_path: &Path) -> Result<Vec<PathBuf>, ParseError> {
     let mut paths: Vec<PathBuf> = vec![];
 
-    let har_str = std::fs::read_to_string(path).expect(&format!("Cannot read HAR file"));
+    let har_str = std::fs::read_to_string(har_path).map_err(|err| {
+        debug!("Unable to read {}: {}", har_path.display(), err);
+        ParseError::CannotOpenHarFile
+    })?;
 
     // Parse the HAR file
-    let har: Har = serde_json::from_str(&har_str).expect("Invalid HAR file");
+    let har: Har = serde_json::from_str(&har_str).map_err(|err| {
+        debug!("Unable to parse {}: {}", har_path.display(), err);
+        ParseError::CannotParseHarFile
+    })?;
 
     for entry in har.log.entries {
         if entry.response.content.mime_type != "application/dash+xml" {
@@ -68,41 +76,42 @@ pub fn extract_mpd(har_path_string: &String, output_dir_path: &PathBuf) -> Vec<P
             continue;
         }
 
-        let text = &entry
-            .response
-            .content
-            .text
-            .expect("Found dash+xml entry with no text");
-
         let full_url = &entry.request.url;
 
-        let fallback_header = Header {
-            name: "date".to_owned(),
-            value: "Unknown Date".to_owned(),
+        let Some(text) = &entry.response.content.text else {
+            eprintln!("Skipping dash+xml entry with no text {}", full_url);
+            continue;
         };
 
-        let date = &entry
+        let date = entry
             .response
             .headers
             .iter()
-            .find(|header| header.name == "date")
-            .unwrap_or(&fallback_header)
-            .value;
-
-        let parsed_date = DateTime::parse_from_rfc2822(date).expect("Failed to parse date");
+            .find(|header| header.name.eq_ignore_ascii_case("date"))
+            .map(|header| header.value.as_str());
 
-        let formatted_date = parsed_date.format("%Y-%m-%d-%H-%M-%S").to_string();
+        let formatted_date = match date.map(DateTime::parse_from_rfc2822) {
+            Some(Ok(parsed_date)) => parsed_date.format("%Y-%m-%d-%H-%M-%S").to_string(),
+            _ => "unknown-date".to_owned(),
+        };
 
         // Parse the URL
-        let url = Url::parse(full_url).expect("Failed to parse URL");
+        let url = match Url::parse(full_url) {
+            Ok(url) => url,
+            Err(err) => {
+                eprintln!("Skipping entry with invalid URL {}: {}", full_url, err);
+                continue;
+            }
+        };
 
         // Extract the path segments
         let path_segments: Vec<&str> = url.path_segments().map(|c| c.collect()).unwrap_or_default();
 
         // Get the last segment which is the filename
-        let filename = path_segments
-            .last()
-            .expect(&format!("Could not parse manifest filename {}", full_url));
+        let Some(filename) = path_segments.last() else {
+            eprintln!("Could not parse manifest filename {}", full_url);
+            continue;
+        };
 
         let filename = format!("{}-{}", formatted_date, filename);
 
@@ -110,17 +119,17 @@ pub fn extract_mpd(har_path_string: &String, output_dir_path: &PathBuf) -> Vec<P
 
         println!("Writing {}", path.display());
 
-        let mut file = File::create(&path).expect("Error creating file");
+        let result = File::create(&path).and_then(|mut file| file.write_all(text.as_bytes()));
 
-        match file.write_all(text.as_bytes()) {
+        match result {
             Ok(_r) => {
                 println!("Saved {}", filename);
 
                 paths.push(path);
             }
-            Err(e) => panic!("Unable to save {}: {:?}", filename, e),
+            Err(e) => eprintln!("Unable to save {}: {:?}", filename, e),
         }
     }
 
-    paths
+    Ok(paths)
 }
@@ -1,134 +1,544 @@
-use dash_mpd::{AdaptationSet, Period, Representation, SegmentTemplate};
+use std::{path::Path, time::Duration};
 
-use crate::expanded::{ExpandedSegmentTimeline, ExpandedSegmentTimelineSegment, ExpandedSegments};
-use crate::util::error::ParseError;
+use chrono::Utc;
+use dash_mpd::{
+    AdaptationSet, BaseURL, Period, Representation, SegmentBase, SegmentList, SegmentTemplate,
+    SegmentTimeline, MPD,
+};
+
+use crate::expanded::{
+    ContentType, Expanded, ExpandedLive, ExpandedSegmentTimeline, ExpandedSegmentTimelineSegment,
+    ExpandedSegments, MediaTime,
+};
+use crate::util::{
+    error::{ParseError, ParseWarning},
+    sidx::read_sidx,
+    source::SourceElement,
+};
 
 use crate::debug;
 
 pub fn describe_representation(
     representation: &Representation,
     adaptation_set: &AdaptationSet,
-) -> String {
+    content_type: ContentType,
+) -> Result<String, ParseError> {
     let mime_type = representation
         .mimeType
         .as_ref()
         .or(adaptation_set.mimeType.as_ref())
-        .expect(&ParseError::CannotInferRepresentationMimeType.describe());
+        .ok_or(ParseError::CannotInferRepresentationMimeType)?;
     let codecs = representation
         .codecs
         .as_ref()
-        .or(adaptation_set.codecs.as_ref())
-        .expect(&ParseError::CannotInferRepresentationCodecs.describe());
+        .or(adaptation_set.codecs.as_ref());
+
+    Ok(match content_type {
+        ContentType::Audio => {
+            let codecs = codecs.ok_or(ParseError::CannotInferRepresentationCodecs)?;
 
-    match &adaptation_set.contentType.as_deref() {
-        Some("audio") => {
             let audio_sampling_rate = representation
                 .audioSamplingRate
                 .as_ref()
                 .or(adaptation_set.audioSamplingRate.as_ref())
-                .expect(&ParseError::CannotInferRepresentationAudioSamplingRate.describe());
+                .ok_or(ParseError::CannotInferRepresentationAudioSamplingRate)?;
 
             format!("{} {} {}Hz", mime_type, codecs, audio_sampling_rate)
         }
-        Some("video") => {
+        ContentType::Video => {
+            let codecs = codecs.ok_or(ParseError::CannotInferRepresentationCodecs)?;
+
             let frame_rate = representation
                 .frameRate
                 .as_ref()
                 .or(adaptation_set.frameRate.as_ref())
-                .expect(&ParseError::CannotInferRepresentationFrameRate.describe());
+                .ok_or(ParseError::CannotInferRepresentationFrameRate)?;
 
             let width = representation
                 .width
-                .expect(&ParseError::RepresentationWithoutWidth.describe());
+                .ok_or(ParseError::RepresentationWithoutWidth)?;
 
             let height = representation
                 .height
-                .expect(&ParseError::RepresentationWithoutHeight.describe());
+                .ok_or(ParseError::RepresentationWithoutHeight)?;
 
             let bandwidth = representation
                 .bandwidth
-                .expect(&ParseError::RepresentationWithoutBandwidth.describe());
+                .ok_or(ParseError::RepresentationWithoutBandwidth)?;
 
             format!(
                 "{} {} {}x{} {}fps {}bps",
                 mime_type, codecs, width, height, frame_rate, bandwidth,
             )
         }
-        _ => {
-            panic!(
-                "{}",
-                &ParseError::UnmappedRepresentationContentType.describe()
-            )
+        ContentType::Text => {
+            // Sidecar subtitles such as text/vtt usually carry no codecs
+            let lang = representation
+                .lang
+                .as_ref()
+                .or(adaptation_set.lang.as_ref());
+
+            let mut description = mime_type.to_owned();
+
+            if let Some(codecs) = codecs {
+                description.push_str(&format!(" {}", codecs));
+            }
+
+            if let Some(lang) = lang {
+                description.push_str(&format!(" lang={}", lang));
+            }
+
+            description
+        }
+        ContentType::Image => {
+            let width = representation
+                .width
+                .ok_or(ParseError::RepresentationWithoutWidth)?;
+
+            let height = representation
+                .height
+                .ok_or(ParseError::RepresentationWithoutHeight)?;
+
+            // Thumbnail grids are signalled as an EssentialProperty with a value of "colsxrows"
+            let tiles = representation
+                .essential_property
+                .iter()
+                .chain(adaptation_set.essential_property.iter())
+                .find(|property| property.schemeIdUri.ends_with("thumbnail_tile"))
+                .and_then(|property| property.value.as_ref());
+
+            match tiles {
+                Some(tiles) => format!("{} {}x{} {} tiles", mime_type, width, height, tiles),
+                None => format!("{} {}x{}", mime_type, width, height),
+            }
+        }
+        ContentType::Application => {
+            let mut description = mime_type.to_owned();
+
+            if let Some(codecs) = codecs {
+                description.push_str(&format!(" {}", codecs));
+            }
+
+            if let Some(bandwidth) = representation.bandwidth {
+                description.push_str(&format!(" {}bps", bandwidth));
+            }
+
+            description
+        }
+    })
+}
+
+/// Resolution, bitrate, codecs and sample rate of a Representation, each that is known on a line
+/// of its own. Unlike `describe_representation` nothing is required.
+pub fn summarise_representation(
+    representation: &Representation,
+    adaptation_set: &AdaptationSet,
+) -> Vec<String> {
+    let mut summary = vec![];
+
+    if let (Some(width), Some(height)) = (representation.width, representation.height) {
+        summary.push(format!("{}x{}", width, height));
+    }
+
+    if let Some(bandwidth) = representation.bandwidth {
+        summary.push(format_bitrate(bandwidth));
+    }
+
+    if let Some(codecs) = representation
+        .codecs
+        .as_ref()
+        .or(adaptation_set.codecs.as_ref())
+    {
+        summary.push(codecs.clone());
+    }
+
+    if let Some(audio_sampling_rate) = representation
+        .audioSamplingRate
+        .as_ref()
+        .or(adaptation_set.audioSamplingRate.as_ref())
+    {
+        match audio_sampling_rate.parse::<f64>() {
+            Ok(rate) => summary.push(format!("{}kHz", rate / 1000.0)),
+            Err(_) => summary.push(format!("{}Hz", audio_sampling_rate)),
+        }
+    }
+
+    summary
+}
+
+/// e.g. 128kbps or 4.5Mbps
+fn format_bitrate(bandwidth: u64) -> String {
+    if bandwidth >= 1_000_000 {
+        format!("{}Mbps", (bandwidth as f64 / 100_000.0).round() / 10.0)
+    } else if bandwidth >= 1000 {
+        format!("{}kbps", (bandwidth as f64 / 1000.0).round())
+    } else {
+        format!("{}bps", bandwidth)
+    }
+}
+
+/// Substitutes the identifiers of a SegmentTemplate@media, including width formatted ones such
+/// as $Number%05d$. Unknown identifiers are left as they are.
+pub fn resolve_media_template(
+    media: &str,
+    representation_id: &str,
+    number: u64,
+    time: u64,
+    bandwidth: Option<u64>,
+) -> String {
+    let mut url = String::new();
+    let mut parts = media.split('$');
+
+    if let Some(first) = parts.next() {
+        url.push_str(first);
+    }
+
+    // Identifiers sit between pairs of $, so every other part is one
+    while let Some(identifier) = parts.next() {
+        let literal = parts.next();
+
+        if literal.is_none() {
+            // An unmatched $ is kept as is
+            url.push('$');
+            url.push_str(identifier);
+            break;
+        }
+
+        let (name, format) = identifier.split_once('%').unwrap_or((identifier, ""));
+
+        let value = match name {
+            "" => Some("$".to_owned()),
+            "RepresentationID" => Some(representation_id.to_owned()),
+            "Number" => Some(format_template_value(number, format)),
+            "Time" => Some(format_template_value(time, format)),
+            "Bandwidth" => bandwidth.map(|bandwidth| format_template_value(bandwidth, format)),
+            _ => None,
+        };
+
+        match value {
+            Some(value) => url.push_str(&value),
+            None => {
+                url.push('$');
+                url.push_str(identifier);
+                url.push('$');
+            }
         }
+
+        url.push_str(literal.unwrap_or_default());
+    }
+
+    url
+}
+
+/// Applies a printf style width tag, e.g. 05d
+fn format_template_value(value: u64, format: &str) -> String {
+    match format
+        .strip_suffix('d')
+        .and_then(|width| width.parse::<usize>().ok())
+    {
+        Some(width) => format!("{:0width$}", value, width = width),
+        None => value.to_string(),
+    }
+}
+
+/// Determines the content type of an AdaptationSet. @contentType is often left out, so it is
+/// inferred from @mimeType, then the codecs, then the presence of video or audio attributes.
+pub fn parse_content_type(
+    adaptation_set: &AdaptationSet,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<ContentType, ParseError> {
+    if let Some(content_type) = adaptation_set
+        .contentType
+        .as_deref()
+        .and_then(ContentType::from_str)
+    {
+        return Ok(content_type);
     }
+
+    let (content_type, from) = infer_content_type(adaptation_set).ok_or_else(|| {
+        debug!(
+            "  No contentType inferred, checked @contentType {:?}, @mimeType, @codecs, @width, @height and @audioSamplingRate",
+            adaptation_set.contentType
+        );
+
+        ParseError::AdaptationSetWithoutContentType
+    })?;
+
+    debug!(
+        "  contentType {} inferred from {}",
+        content_type.as_str(),
+        from
+    );
+
+    warnings.push(ParseWarning::ContentTypeInferred { content_type, from });
+
+    Ok(content_type)
 }
 
-pub fn parse_period_start_ms(period: &Period, previous_period_end_ms: u64) -> u64 {
-    match period.start {
+/// The content type of an AdaptationSet without a usable @contentType and the attribute it was
+/// inferred from, in order of @mimeType, @codecs, @width/@height then @audioSamplingRate
+fn infer_content_type(adaptation_set: &AdaptationSet) -> Option<(ContentType, String)> {
+    let codecs = adaptation_set.codecs.as_deref().or(adaptation_set
+        .representations
+        .iter()
+        .find_map(|rep| rep.codecs.as_deref()));
+
+    if let Some(mime_type) = &adaptation_set.mimeType {
+        if let Some(content_type) = content_type_from_mime_type(mime_type, codecs) {
+            return Some((
+                content_type,
+                format!("AdaptationSet@mimeType {}", mime_type),
+            ));
+        }
+    }
+
+    for rep in adaptation_set.representations.iter() {
+        let rep_codecs = rep.codecs.as_deref().or(codecs);
+
+        if let Some(mime_type) = rep.mimeType.as_deref() {
+            if let Some(content_type) = content_type_from_mime_type(mime_type, rep_codecs) {
+                return Some((
+                    content_type,
+                    format!("Representation@mimeType {}", mime_type),
+                ));
+            }
+        }
+    }
+
+    if let Some(codecs) = codecs {
+        if let Some(content_type) = content_type_from_codecs(codecs) {
+            return Some((content_type, format!("@codecs {}", codecs)));
+        }
+    }
+
+    let has_dimensions = adaptation_set.width.is_some()
+        || adaptation_set.height.is_some()
+        || adaptation_set
+            .representations
+            .iter()
+            .any(|rep| rep.width.is_some() || rep.height.is_some());
+
+    if has_dimensions {
+        return Some((ContentType::Video, "@width/@height".to_owned()));
+    }
+
+    let has_sampling_rate = adaptation_set.audioSamplingRate.is_some()
+        || adaptation_set
+            .representations
+            .iter()
+            .any(|rep| rep.audioSamplingRate.is_some());
+
+    if has_sampling_rate {
+        return Some((ContentType::Audio, "@audioSamplingRate".to_owned()));
+    }
+
+    None
+}
+
+fn content_type_from_mime_type(mime_type: &str, codecs: Option<&str>) -> Option<ContentType> {
+    match mime_type.split_once('/') {
+        // Subtitles are commonly packaged as ISOBMFF (stpp, wvtt) or raw TTML
+        Some(("application", "ttml+xml")) => Some(ContentType::Text),
+        Some(("application", "mp4")) => match codecs.and_then(content_type_from_codecs) {
+            Some(ContentType::Text) => {
+                debug!("  application/mp4 with codecs {:?} is subtitles", codecs);
+
+                Some(ContentType::Text)
+            }
+            _ => Some(ContentType::Application),
+        },
+        Some((top_level, _)) => ContentType::from_str(top_level),
+        None => None,
+    }
+}
+
+fn content_type_from_codecs(codecs: &str) -> Option<ContentType> {
+    // Only the first codec of a list is needed, and only its four character code
+    let codec = codecs.split(',').next()?.trim();
+    let fourcc = codec.split('.').next()?;
+
+    match fourcc {
+        "avc1" | "avc3" | "hev1" | "hvc1" | "dvh1" | "dvhe" | "vp08" | "vp09" | "av01" => {
+            Some(ContentType::Video)
+        }
+        "mp4a" | "ac-3" | "ec-3" | "ac-4" | "opus" | "Opus" | "flac" | "fLaC" | "dtsc" | "dtse"
+        | "dtsx" | "mha1" | "mhm1" => Some(ContentType::Audio),
+        "stpp" | "wvtt" | "tx3g" => Some(ContentType::Text),
+        _ => None,
+    }
+}
+
+/// Period@start, or the end of the previous Period. The first Period starts at 0 without one.
+pub fn parse_period_start(
+    period: &Period,
+    previous_period_end: Option<MediaTime>,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<MediaTime, ParseError> {
+    Ok(match period.start {
         Some(duration) => {
-            let start_ms: u64 = duration
-                .as_millis()
-                .try_into()
-                .expect(&ParseError::CannotParsePeriodStartAsU64.describe());
-
-            let gap: i64 = match start_ms.cmp(&previous_period_end_ms) {
-                std::cmp::Ordering::Greater => (start_ms - previous_period_end_ms) as i64,
-                std::cmp::Ordering::Less => -1 * (previous_period_end_ms - start_ms) as i64,
+            let start = MediaTime::from_duration(duration)
+                .ok_or(ParseError::CannotParsePeriodStartAsU64)?;
+
+            let previous_period_end = previous_period_end.unwrap_or(MediaTime::ZERO);
+
+            let gap: i64 = match start.cmp(&previous_period_end) {
+                std::cmp::Ordering::Greater => {
+                    start.saturating_sub(previous_period_end).as_ms() as i64
+                }
+                std::cmp::Ordering::Less => {
+                    -(previous_period_end.saturating_sub(start).as_ms() as i64)
+                }
                 std::cmp::Ordering::Equal => 0,
             };
 
             debug!(
                 "  Start time {}ms. {}ms gap to the previous period.",
-                start_ms, gap
+                start.as_ms(),
+                gap
             );
 
-            start_ms
+            start
         }
-        None => {
-            debug!(
-                "No start time defined, using the end time of the previous period (or 0), {}",
-                previous_period_end_ms
-            );
+        None => match previous_period_end {
+            Some(previous_period_end) => {
+                debug!(
+                    "No start time defined, using the end time of the previous period, {}",
+                    previous_period_end.as_ms()
+                );
 
-            previous_period_end_ms
-        }
-    }
+                warnings.push(ParseWarning::PeriodStartInferredFromPreviousPeriod);
+
+                previous_period_end
+            }
+            None => MediaTime::ZERO,
+        },
+    })
 }
 
-pub fn parse_period_duration_ms(period: &Period) -> Option<u64> {
-    match period.duration {
+pub fn parse_period_duration(period: &Period) -> Result<Option<MediaTime>, ParseError> {
+    Ok(match period.duration {
         Some(duration) => {
-            let duration_ms: u64 = duration
-                .as_millis()
-                .try_into()
-                .expect(&ParseError::CannotParsePeriodDurationAsU64.describe());
-            debug!("  Duration {}ms.", duration_ms);
+            let duration = MediaTime::from_duration(duration)
+                .ok_or(ParseError::CannotParsePeriodDurationAsU64)?;
+            debug!("  Duration {}ms.", duration.as_ms());
 
-            Some(duration_ms)
+            Some(duration)
         }
         None => {
             debug!("No duration defined. Period ends naturally when its segments end (?).",);
 
             None
         }
+    })
+}
+
+/// Resolves where a Period ends, used to bound @duration based segment addressing. In order of
+/// preference: Period@duration, the next Period@start, then MPD@mediaPresentationDuration.
+pub fn parse_period_end(
+    period_start: MediaTime,
+    period_duration: Option<MediaTime>,
+    next_period: Option<&Period>,
+    media_presentation_duration: Option<Duration>,
+) -> Option<MediaTime> {
+    if let Some(duration) = period_duration {
+        return Some(period_start + duration);
+    }
+
+    let next_period_start = next_period
+        .and_then(|p| p.start)
+        .and_then(MediaTime::from_duration);
+
+    if next_period_start.is_some() {
+        return next_period_start;
+    }
+
+    // mediaPresentationDuration only bounds the last Period
+    match (next_period, media_presentation_duration) {
+        (None, Some(duration)) => MediaTime::from_duration(duration),
+        _ => None,
+    }
+}
+
+/// For dynamic manifests, maps presentation time to UTC and resolves the availability window at
+/// MPD@publishTime, or now when not present.
+pub fn parse_live(mpd: &MPD) -> Result<Option<ExpandedLive>, ParseError> {
+    if mpd.mpdtype.as_deref() != Some("dynamic") {
+        return Ok(None);
     }
+
+    let availability_start_time = mpd
+        .availabilityStartTime
+        .ok_or(ParseError::MpdWithoutAvailabilityStartTime)?;
+
+    let publish_time = mpd.publishTime.unwrap_or_else(|| {
+        debug!("No publishTime, using the current time as the live edge");
+
+        Utc::now()
+    });
+
+    let live_edge = MediaTime::from_ms(
+        (publish_time - availability_start_time)
+            .num_milliseconds()
+            .max(0) as u64,
+    );
+
+    let time_shift_buffer_depth = mpd.timeShiftBufferDepth.and_then(MediaTime::from_duration);
+
+    let window_start = match time_shift_buffer_depth {
+        Some(depth) => live_edge.saturating_sub(depth),
+        None => MediaTime::ZERO,
+    };
+
+    let live = ExpandedLive {
+        availability_start_time,
+        publish_time,
+        time_shift_buffer_depth,
+        suggested_presentation_delay: mpd
+            .suggestedPresentationDelay
+            .and_then(MediaTime::from_duration),
+        window_start,
+        live_edge,
+    };
+
+    debug!(
+        "Live window {}ms - {}ms ({} - {}), presentation edge {}ms",
+        window_start.as_ms(),
+        live_edge.as_ms(),
+        live.to_utc(window_start).to_rfc3339(),
+        live.to_utc(live_edge).to_rfc3339(),
+        live.presentation_edge().as_ms()
+    );
+
+    Ok(Some(live))
 }
 
 pub fn parse_segment_template(
     representation_segment_template: &Option<SegmentTemplate>,
     adaptation_segment_template: &Option<SegmentTemplate>,
     period_segment_template: &Option<SegmentTemplate>,
-    period_start_ms: u64,
-) -> ExpandedSegments {
-    let timescale = [
-        representation_segment_template,
-        adaptation_segment_template,
-        period_segment_template,
-    ]
-    .iter()
-    .filter_map(|opt| opt.as_ref().and_then(|st| st.timescale))
-    .next()
-    .expect("No timescale available");
+    period_start: MediaTime,
+    period_end: Option<MediaTime>,
+    live_window: Option<(MediaTime, MediaTime)>,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<ExpandedSegments, ParseError> {
+    if representation_segment_template.is_none() {
+        if adaptation_segment_template.is_some() {
+            warnings.push(ParseWarning::SegmentTemplateInheritedFromAdaptationSet);
+        } else if period_segment_template.is_some() {
+            warnings.push(ParseWarning::SegmentTemplateInheritedFromPeriod);
+        }
+    }
+
+    let (timescale, warning) = inherited_timescale(
+        [
+            representation_segment_template,
+            adaptation_segment_template,
+            period_segment_template,
+        ]
+        .map(|opt| opt.as_ref().and_then(|st| st.timescale)),
+    );
+
+    warnings.extend(warning);
 
     let timeline = [
         representation_segment_template,
@@ -137,8 +547,7 @@ pub fn parse_segment_template(
     ]
     .iter()
     .filter_map(|opt| opt.as_ref().and_then(|st| st.SegmentTimeline.as_ref()))
-    .next()
-    .expect("No SegmentTimeline available");
+    .next();
 
     let media = [
         representation_segment_template,
@@ -148,7 +557,7 @@ pub fn parse_segment_template(
     .iter()
     .filter_map(|opt| opt.as_ref().and_then(|st| st.media.as_ref()))
     .next()
-    .expect("No SegmentTemplate media available");
+    .ok_or(ParseError::SegmentTemplateWithoutMedia)?;
 
     let presentation_time_offset = [
         representation_segment_template,
@@ -158,7 +567,10 @@ pub fn parse_segment_template(
     .iter()
     .filter_map(|opt| opt.as_ref().and_then(|st| st.presentationTimeOffset))
     .next()
-    .unwrap_or(0);
+    .unwrap_or_else(|| {
+        warnings.push(ParseWarning::DefaultedPresentationTimeOffset);
+        0
+    });
 
     if media.contains("$Time$") {
         debug!("  Media template contains $Time$ placeholder");
@@ -166,59 +578,523 @@ pub fn parse_segment_template(
         debug!("  Media template contains $Number$ placeholder");
     }
 
+    let start_number = [
+        representation_segment_template,
+        adaptation_segment_template,
+        period_segment_template,
+    ]
+    .iter()
+    .filter_map(|opt| opt.as_ref().and_then(|st| st.startNumber))
+    .next()
+    .unwrap_or(1);
+
+    let (segment_timeline, start_number) = match timeline {
+        Some(timeline) => (
+            expand_segment_timeline(
+                timeline,
+                timescale,
+                presentation_time_offset,
+                period_start,
+                period_end,
+                live_window.map(|(_, live_edge)| live_edge),
+            )?,
+            start_number,
+        ),
+        None => {
+            let duration = [
+                representation_segment_template,
+                adaptation_segment_template,
+                period_segment_template,
+            ]
+            .iter()
+            .filter_map(|opt| opt.as_ref().and_then(|st| st.duration))
+            .find(|duration| *duration > 0.0)
+            .ok_or(ParseError::SegmentTemplateWithoutDuration)?;
+
+            let segment_timeline = expand_segment_duration(
+                duration,
+                timescale,
+                presentation_time_offset,
+                start_number,
+                period_start,
+                period_end,
+                live_window,
+            )?;
+
+            // A live window may begin part way through the Period
+            let skipped = segment_timeline
+                .start()
+                .saturating_sub(period_start)
+                .div_floor(segment_timeline.segments[0].segment_duration);
+
+            (segment_timeline, start_number + skipped)
+        }
+    };
+
+    Ok(ExpandedSegments::SegmentTemplate {
+        segment_timeline,
+        media: media.to_owned(),
+        start_number,
+    })
+}
+
+/// An attribute of the `element` child of the most specific of `levels` that has it, read from
+/// the manifest source. For the attributes dash-mpd does not parse, e.g.
+/// SegmentList@presentationTimeOffset.
+pub fn source_attribute(
+    levels: &[Option<&SourceElement>],
+    element: &str,
+    attribute: &str,
+) -> Option<String> {
+    levels
+        .iter()
+        .flatten()
+        .filter_map(|level| level.first(element))
+        .find_map(|element| element.attribute(attribute))
+}
+
+/// The @timescale of the most specific of `levels` that has one. It defaults to 1 when not
+/// present on any level, with a warning.
+fn inherited_timescale(levels: [Option<u64>; 3]) -> (u64, Option<ParseWarning>) {
+    match levels
+        .into_iter()
+        .flatten()
+        .find(|timescale| *timescale > 0)
+    {
+        Some(timescale) => (timescale, None),
+        None => (1, Some(ParseWarning::DefaultedTimescale)),
+    }
+}
+
+/// `lists` are the SegmentLists of the Representation, AdaptationSet and Period, in that order.
+/// `presentation_time_offset` is SegmentList@presentationTimeOffset as inherited from the same
+/// levels, see `source_attribute`.
+pub fn parse_segment_list(
+    lists: [&Option<SegmentList>; 3],
+    presentation_time_offset: Option<String>,
+    period_start: MediaTime,
+    period_end: Option<MediaTime>,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<ExpandedSegments, ParseError> {
+    let [representation_segment_list, adaptation_segment_list, period_segment_list] = lists;
+
+    if representation_segment_list.is_none() {
+        if adaptation_segment_list.is_some() {
+            warnings.push(ParseWarning::SegmentListInheritedFromAdaptationSet);
+        } else if period_segment_list.is_some() {
+            warnings.push(ParseWarning::SegmentListInheritedFromPeriod);
+        }
+    }
+
+    let (timescale, warning) =
+        inherited_timescale(lists.map(|opt| opt.as_ref().and_then(|sl| sl.timescale)));
+
+    warnings.extend(warning);
+
+    let presentation_time_offset = presentation_time_offset
+        .and_then(|offset| offset.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            warnings.push(ParseWarning::DefaultedPresentationTimeOffset);
+            0
+        });
+
+    let duration = lists
+        .iter()
+        .filter_map(|opt| opt.as_ref().and_then(|sl| sl.duration))
+        .next();
+
+    let timeline = lists
+        .iter()
+        .filter_map(|opt| opt.as_ref().and_then(|sl| sl.SegmentTimeline.as_ref()))
+        .next();
+
+    // SegmentURLs are not merged between levels, the most specific non-empty list wins
+    let segment_urls = lists
+        .iter()
+        .filter_map(|opt| opt.as_ref().map(|sl| &sl.segment_urls))
+        .find(|urls| !urls.is_empty())
+        .ok_or(ParseError::SegmentListWithoutSegments)?;
+
+    debug!("  SegmentList with {} SegmentURLs", segment_urls.len());
+
+    let segment_timeline = match (timeline, duration) {
+        (Some(timeline), _) => {
+            let segment_timeline = expand_segment_timeline(
+                timeline,
+                timescale,
+                presentation_time_offset,
+                period_start,
+                period_end,
+                None,
+            )?;
+
+            let timeline_count: u64 = segment_timeline
+                .segments
+                .iter()
+                .map(|s| s.segment_count)
+                .sum();
+
+            if timeline_count != segment_urls.len() as u64 {
+                debug!(
+                    "  SegmentTimeline describes {} segments but the SegmentList has {} SegmentURLs",
+                    timeline_count,
+                    segment_urls.len()
+                );
+            }
+
+            segment_timeline
+        }
+        (None, Some(duration)) => {
+            let segment_count = segment_urls.len() as u64;
+            let segment_duration = MediaTime::new(duration, timescale);
+            let duration = segment_duration * segment_count;
+
+            debug!(
+                "  @duration={} ({}ms). {} segments, ending at {}ms",
+                segment_duration.ticks,
+                segment_duration.as_ms(),
+                segment_count,
+                (period_start + duration).as_ms()
+            );
+
+            ExpandedSegmentTimeline {
+                segments: vec![ExpandedSegmentTimelineSegment {
+                    start: period_start,
+                    duration,
+                    end: period_start + duration,
+                    segment_duration,
+                    segment_count,
+                    presentation_time_offset,
+                }],
+            }
+        }
+        (None, None) => return Err(ParseError::SegmentListWithoutDuration),
+    };
+
+    Ok(ExpandedSegments::SegmentList {
+        segment_timeline,
+        media: segment_urls
+            .iter()
+            .map(|segment_url| segment_url.media.clone().unwrap_or_default())
+            .collect(),
+    })
+}
+
+/// A SegmentBase on `level` in the manifest source, for the AdaptationSet and Period levels
+/// dash-mpd does not parse it on
+pub fn segment_base_from_source(level: Option<&SourceElement>) -> Option<SegmentBase> {
+    segment_information_from_source(level, "SegmentBase")?.SegmentBase
+}
+
+/// A SegmentList on `level` in the manifest source, for the Period level dash-mpd does not parse
+/// it on
+pub fn segment_list_from_source(level: Option<&SourceElement>) -> Option<SegmentList> {
+    segment_information_from_source(level, "SegmentList")?.SegmentList
+}
+
+/// Parses the `name` child of `level` again on its own, as if it were on a Representation
+fn segment_information_from_source(
+    level: Option<&SourceElement>,
+    name: &str,
+) -> Option<Representation> {
+    let source = level?.first(name)?.source.as_ref()?;
+
+    let xml = format!(
+        r#"<MPD xmlns="urn:mpeg:dash:schema:mpd:2011"><Period><AdaptationSet><Representation>{}</Representation></AdaptationSet></Period></MPD>"#,
+        source
+    );
+
+    let mut mpd = dash_mpd::parse(&xml)
+        .map_err(|err| debug!("  Unable to parse an inherited {}: {}", name, err))
+        .ok()?;
+
+    mpd.periods.pop()?.adaptations.pop()?.representations.pop()
+}
+
+/// Expands a single file Representation. Without an index it is drawn as one continuous span.
+/// When `media_dir` is provided and the BaseURL is a local file, the sidx box at @indexRange is
+/// read to recover the real subsegment boundaries. Attributes missing from the Representation's
+/// SegmentBase are inherited from the AdaptationSet's, then the Period's, in that order in `bases`.
+pub fn parse_segment_base(
+    bases: [&Option<SegmentBase>; 3],
+    base_urls: &[BaseURL],
+    period_start: MediaTime,
+    period_end: Option<MediaTime>,
+    media_dir: Option<&Path>,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<ExpandedSegments, ParseError> {
+    let [representation_segment_base, adaptation_segment_base, period_segment_base] = bases;
+
+    if representation_segment_base.is_none() {
+        if adaptation_segment_base.is_some() {
+            warnings.push(ParseWarning::SegmentBaseInheritedFromAdaptationSet);
+        } else if period_segment_base.is_some() {
+            warnings.push(ParseWarning::SegmentBaseInheritedFromPeriod);
+        }
+    }
+
+    let (timescale, warning) =
+        inherited_timescale(bases.map(|opt| opt.as_ref().and_then(|sb| sb.timescale)));
+
+    warnings.extend(warning);
+
+    let presentation_time_offset = bases
+        .iter()
+        .filter_map(|opt| opt.as_ref().and_then(|sb| sb.presentationTimeOffset))
+        .next()
+        .unwrap_or(0);
+
+    let presentation_duration = bases
+        .iter()
+        .filter_map(|opt| opt.as_ref().and_then(|sb| sb.presentationDuration))
+        .next();
+
+    let index_range = bases
+        .iter()
+        .filter_map(|opt| opt.as_ref().and_then(|sb| sb.indexRange.as_ref()))
+        .next();
+
+    let sidx = match (media_dir, index_range, base_urls.first()) {
+        (Some(media_dir), Some(index_range), Some(base_url)) if !base_url.base.contains("://") => {
+            let path = media_dir.join(&base_url.base);
+
+            match read_sidx(&path, index_range) {
+                Ok(sidx) => {
+                    debug!(
+                        "  Read sidx from {:?} {}: {} subsegments",
+                        path,
+                        index_range,
+                        sidx.subsegment_durations.len()
+                    );
+
+                    Some(sidx)
+                }
+                Err(err) => {
+                    debug!("  Unable to read sidx, using a single span. {}", err);
+
+                    warnings.push(ParseWarning::SidxNotRead);
+
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    let indexed = sidx.is_some();
+
+    let segment_timeline = match sidx {
+        Some(sidx) => {
+            let presentation_time_offset_time = MediaTime::new(presentation_time_offset, timescale);
+
+            let mut _segments: Vec<ExpandedSegmentTimelineSegment> = vec![];
+
+            let mut time = sidx.earliest_presentation_time;
+
+            // Group consecutive subsegments of identical duration, the same way <S> elements do
+            for run in sidx.subsegment_durations.chunk_by(|a, b| a == b) {
+                let segment_count = run.len() as u64;
+                let segment_duration = MediaTime::new(run[0], sidx.timescale);
+
+                let start = period_start
+                    + MediaTime::new(time, sidx.timescale)
+                        .saturating_sub(presentation_time_offset_time);
+                let duration = segment_duration * segment_count;
+
+                time += run[0] * segment_count;
+
+                _segments.push(ExpandedSegmentTimelineSegment {
+                    start,
+                    duration,
+                    end: start + duration,
+                    segment_duration,
+                    segment_count,
+                    presentation_time_offset,
+                });
+            }
+
+            ExpandedSegmentTimeline {
+                segments: _segments,
+            }
+        }
+        None => {
+            let duration = presentation_duration
+                .map(|duration| MediaTime::new(duration, timescale))
+                .or(period_end.map(|end| end.saturating_sub(period_start)))
+                .ok_or(ParseError::SegmentBaseWithoutDuration)?;
+
+            debug!(
+                "  SegmentBase {}ms, ending at {}ms",
+                duration.as_ms(),
+                (period_start + duration).as_ms()
+            );
+
+            ExpandedSegmentTimeline {
+                segments: vec![ExpandedSegmentTimelineSegment {
+                    start: period_start,
+                    duration,
+                    end: period_start + duration,
+                    segment_duration: duration,
+                    segment_count: 1,
+                    presentation_time_offset,
+                }],
+            }
+        }
+    };
+
+    Ok(ExpandedSegments::SegmentBase {
+        segment_timeline,
+        indexed,
+        base_url: base_urls.first().map(|base_url| base_url.base.clone()),
+    })
+}
+
+/// Expands @duration based addressing (no SegmentTimeline) into a single run of equal length
+/// segments. Static manifests run to the end of the Period, the last segment may be partial.
+/// Dynamic manifests only include the complete segments inside the availability window.
+fn expand_segment_duration(
+    duration: f64,
+    timescale: u64,
+    presentation_time_offset: u64,
+    start_number: u64,
+    period_start: MediaTime,
+    period_end: Option<MediaTime>,
+    live_window: Option<(MediaTime, MediaTime)>,
+) -> Result<ExpandedSegmentTimeline, ParseError> {
+    // @duration is an unsigned int in the schema, fractional values are rounded to whole ticks
+    let segment_duration = MediaTime::new((duration.round() as u64).max(1), timescale);
+
+    let (first_index, last_index) = match (period_end, live_window) {
+        (_, Some((window_start, live_edge))) => {
+            let end = period_end.map_or(live_edge, |end| end.min(live_edge));
+            let start = window_start.max(period_start);
+
+            (
+                start
+                    .saturating_sub(period_start)
+                    .div_floor(segment_duration),
+                end.saturating_sub(period_start).div_floor(segment_duration),
+            )
+        }
+        (Some(end), None) => (
+            0,
+            end.saturating_sub(period_start).div_ceil(segment_duration),
+        ),
+        (None, None) => return Err(ParseError::CannotInferSegmentTemplateEnd),
+    };
+
+    let segment_count = last_index.saturating_sub(first_index);
+
+    // e.g. a past Period ending before the live window starts
+    if segment_count == 0 {
+        return Err(ParseError::SegmentTemplateWithoutSegments);
+    }
+
+    let start = period_start + segment_duration * first_index;
+    let duration = segment_duration * segment_count;
+
+    debug!(
+        "  @duration={} ({}ms). $Number$ {} to {}, ending at {}ms",
+        segment_duration.ticks,
+        segment_duration.as_ms(),
+        start_number + first_index,
+        start_number + first_index + segment_count.saturating_sub(1),
+        (start + duration).as_ms()
+    );
+
+    Ok(ExpandedSegmentTimeline {
+        segments: vec![ExpandedSegmentTimelineSegment {
+            start,
+            duration,
+            end: start + duration,
+            segment_duration,
+            segment_count,
+            presentation_time_offset,
+        }],
+    })
+}
+
+fn expand_segment_timeline(
+    timeline: &SegmentTimeline,
+    timescale: u64,
+    presentation_time_offset: u64,
+    period_start: MediaTime,
+    period_end: Option<MediaTime>,
+    live_edge: Option<MediaTime>,
+) -> Result<ExpandedSegmentTimeline, ParseError> {
     let mut _segments: Vec<ExpandedSegmentTimelineSegment> = vec![];
 
+    // Bounds for negative @r, in ticks on the same scale as S@t
+    let to_ticks = |time: MediaTime| {
+        time.saturating_sub(period_start).to_timescale(timescale) + presentation_time_offset
+    };
+    let period_end_ticks = period_end.map(to_ticks);
+    let live_edge_ticks = live_edge.map(to_ticks);
+
     let mut running_time_unit: u64 = timeline
         .segments
         .first()
-        .expect(&ParseError::SegmentTimelineWithoutSegments.describe())
+        .ok_or(ParseError::SegmentTimelineWithoutSegments)?
         .t
         .unwrap_or(0);
 
-    for s in timeline.segments.iter() {
-        let segment_repeat = match s.r {
-            Some(r) => r as u64 + 1,
-            None => 1u64,
-        };
-
-        let segment_duration_ticks = s.d as u64;
-        let segment_duration_ms = segment_duration_ticks * 1000 / timescale;
+    for (index, s) in timeline.segments.iter().enumerate() {
+        let segment_duration_ticks = s.d;
+        let segment_duration = MediaTime::new(segment_duration_ticks, timescale);
 
         let segment_t = match s.t {
             Some(t) => t,
             None => running_time_unit,
         };
 
-        running_time_unit = segment_t + segment_duration_ticks * segment_repeat;
+        let segment_repeat = match s.r {
+            Some(r) if r < 0 => resolve_negative_repeat(
+                segment_t,
+                segment_duration_ticks,
+                timeline.segments.get(index + 1).and_then(|next| next.t),
+                period_end_ticks,
+                live_edge_ticks,
+            )?,
+            Some(r) => r as u64 + 1,
+            None => 1u64,
+        };
 
-        //  let start_ticks = segment_t - presentation_time_offset;
+        if segment_repeat == 0 {
+            debug!("  <S> t={} with @r={:?} has no segments", segment_t, s.r);
+            continue;
+        }
+
+        running_time_unit = segment_t + segment_duration_ticks * segment_repeat;
 
-        let segment_element_start_ms =
-            period_start_ms + 1000 * (segment_t - presentation_time_offset) / timescale;
+        let segment_element_start = period_start
+            + MediaTime::new(
+                segment_t.saturating_sub(presentation_time_offset),
+                timescale,
+            );
 
-        let segment_element_duration_ms = segment_duration_ms * segment_repeat;
+        let segment_element_duration = segment_duration * segment_repeat;
 
-        let segment_element_end_ms = segment_element_start_ms + segment_element_duration_ms;
+        let segment_element_end = segment_element_start + segment_element_duration;
 
         debug!(
             "  <S> t={} ({}ms), d={} ({}ms). {} segments, ending at {}ms -- {} {}",
             segment_t,
-            segment_element_start_ms,
+            segment_element_start.as_ms(),
             segment_duration_ticks,
-            segment_duration_ms,
+            segment_duration.as_ms(),
             segment_repeat,
-            segment_element_end_ms,
+            segment_element_end.as_ms(),
             presentation_time_offset,
             timescale
         );
 
         let segment = ExpandedSegmentTimelineSegment {
-            start_ms: segment_element_start_ms,
-            duration_ms: segment_element_duration_ms,
-            end_ms: segment_element_end_ms,
-            segment_duration_ms,
+            start: segment_element_start,
+            duration: segment_element_duration,
+            end: segment_element_end,
+            segment_duration,
             segment_count: segment_repeat,
-            presentation_time_offset: presentation_time_offset,
+            presentation_time_offset,
         };
 
         _segments.push(segment);
@@ -230,11 +1106,305 @@ pub fn parse_segment_template(
     // minus one and an optional @t time attribute. The value of the @t attribute minus the value of the
     // @presentationTimeOffset specifies the MPD start time of the first Segment in the series.
 
-    let _template = ExpandedSegments::SegmentTemplate {
-        segment_timeline: ExpandedSegmentTimeline {
-            segments: _segments,
-        },
+    // e.g. a single S with a negative @r whose S@t is past the live edge
+    if _segments.is_empty() {
+        return Err(ParseError::SegmentTimelineWithoutSegments);
+    }
+
+    Ok(ExpandedSegmentTimeline {
+        segments: _segments,
+    })
+}
+
+/// Resolves the number of segments described by an <S> with a negative @r. Per ISO/IEC 23009-1
+/// the segments repeat until the following S@t, otherwise until the end of the Period. On a live
+/// manifest without a known Period end they repeat up to the live edge.
+fn resolve_negative_repeat(
+    segment_t: u64,
+    segment_duration_ticks: u64,
+    next_t: Option<u64>,
+    period_end_ticks: Option<u64>,
+    live_edge_ticks: Option<u64>,
+) -> Result<u64, ParseError> {
+    if segment_duration_ticks == 0 {
+        return Err(ParseError::CannotResolveNegativeRepeat);
+    }
+
+    let remaining_ticks = |end_ticks: u64| end_ticks.saturating_sub(segment_t);
+
+    let segment_repeat = if let Some(next_t) = next_t {
+        debug!("  Negative @r repeats until the next S@t={}", next_t);
+
+        remaining_ticks(next_t).div_ceil(segment_duration_ticks)
+    } else if let Some(period_end_ticks) = period_end_ticks {
+        debug!(
+            "  Negative @r repeats until the Period end at t={}",
+            period_end_ticks
+        );
+
+        remaining_ticks(period_end_ticks).div_ceil(segment_duration_ticks)
+    } else if let Some(live_edge_ticks) = live_edge_ticks {
+        debug!(
+            "  Negative @r repeats until the live edge at t={}",
+            live_edge_ticks
+        );
+
+        // Only segments that have been fully published are available
+        remaining_ticks(live_edge_ticks) / segment_duration_ticks
+    } else {
+        return Err(ParseError::CannotResolveNegativeRepeat);
     };
 
-    return _template;
+    Ok(segment_repeat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_template_without_timescale() {
+        let segment_template = Some(SegmentTemplate {
+            duration: Some(4.0),
+            media: Some("$Number$.m4s".to_owned()),
+            ..Default::default()
+        });
+
+        let mut warnings = vec![];
+
+        let segments = parse_segment_template(
+            &segment_template,
+            &None,
+            &None,
+            MediaTime::ZERO,
+            Some(MediaTime::from_ms(10_000)),
+            None,
+            &mut warnings,
+        )
+        .unwrap();
+
+        let segment_timeline = segments.segment_timeline();
+
+        // Seconds, with a partial last segment up to the Period end
+        assert_eq!(segment_timeline.segment_count(), 3);
+        assert_eq!(segment_timeline.segments[0].segment_duration.as_ms(), 4000);
+        assert!(warnings
+            .iter()
+            .any(|warning| matches!(warning, ParseWarning::DefaultedTimescale)));
+    }
+
+    #[test]
+    fn segment_template_duration_presentation_time_offset() {
+        let segment_template = Some(SegmentTemplate {
+            timescale: Some(1000),
+            duration: Some(2000.0),
+            presentationTimeOffset: Some(5000),
+            media: Some("$Number$.m4s".to_owned()),
+            ..Default::default()
+        });
+
+        let segments = parse_segment_template(
+            &segment_template,
+            &None,
+            &None,
+            MediaTime::ZERO,
+            Some(MediaTime::from_ms(6000)),
+            None,
+            &mut vec![],
+        )
+        .unwrap();
+
+        let segment_timeline = segments.segment_timeline();
+
+        assert_eq!(segment_timeline.segment_count(), 3);
+        assert_eq!(segment_timeline.segments[0].presentation_time_offset, 5000);
+    }
+
+    #[test]
+    fn segment_template_duration_period_before_the_live_window() {
+        let segment_template = Some(SegmentTemplate {
+            timescale: Some(1000),
+            duration: Some(2000.0),
+            media: Some("$Number$.m4s".to_owned()),
+            ..Default::default()
+        });
+
+        // The Period ends at 30s, the live window is from 60s to 120s
+        let result = parse_segment_template(
+            &segment_template,
+            &None,
+            &None,
+            MediaTime::ZERO,
+            Some(MediaTime::from_ms(30_000)),
+            Some((MediaTime::from_ms(60_000), MediaTime::from_ms(120_000))),
+            &mut vec![],
+        );
+
+        assert!(matches!(
+            result,
+            Err(ParseError::SegmentTemplateWithoutSegments)
+        ));
+    }
+
+    // 2s segments at 48kHz
+    const D: u64 = 96000;
+
+    #[test]
+    fn negative_repeat_up_to_the_next_s() {
+        // 10.02s is 5.01 segments, the last is cut short by the next S@t
+        assert_eq!(
+            resolve_negative_repeat(0, D, Some(481_000), Some(48000 * 60), Some(48000 * 30))
+                .unwrap(),
+            6
+        );
+        assert_eq!(
+            resolve_negative_repeat(48000, D, Some(48000 + 5 * D), None, None).unwrap(),
+            5
+        );
+    }
+
+    #[test]
+    fn negative_repeat_up_to_the_period_end() {
+        // 7s is 3.5 segments, the last is cut short by the Period end
+        assert_eq!(
+            resolve_negative_repeat(0, D, None, Some(48000 * 7), Some(48000 * 30)).unwrap(),
+            4
+        );
+    }
+
+    #[test]
+    fn negative_repeat_up_to_the_live_edge() {
+        // 7s is 3.5 segments, the fourth is not published yet
+        assert_eq!(
+            resolve_negative_repeat(0, D, None, None, Some(48000 * 7)).unwrap(),
+            3
+        );
+        assert_eq!(
+            resolve_negative_repeat(48000 * 8, D, None, None, Some(48000 * 7)).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn negative_repeat_without_an_end() {
+        assert!(matches!(
+            resolve_negative_repeat(0, D, None, None, None),
+            Err(ParseError::CannotResolveNegativeRepeat)
+        ));
+    }
+
+    /// Asserts that `content_type` is inferred for `adaptation_set`, with a warning naming `from`
+    fn assert_inferred(adaptation_set: AdaptationSet, content_type: ContentType, from: &str) {
+        let mut warnings = vec![];
+
+        assert_eq!(
+            parse_content_type(&adaptation_set, &mut warnings).unwrap(),
+            content_type
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].describe().contains(&format!(
+            "No @contentType, {} inferred from {}",
+            content_type.as_str(),
+            from
+        )));
+    }
+
+    fn representation(representation: Representation) -> AdaptationSet {
+        AdaptationSet {
+            representations: vec![representation],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn content_type_from_content_type() {
+        let mut warnings = vec![];
+
+        let adaptation_set = AdaptationSet {
+            contentType: Some("text".to_owned()),
+            mimeType: Some("video/mp4".to_owned()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            parse_content_type(&adaptation_set, &mut warnings).unwrap(),
+            ContentType::Text
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn content_type_from_adaptation_set_mime_type() {
+        let adaptation_set = AdaptationSet {
+            mimeType: Some("audio/mp4".to_owned()),
+            ..Default::default()
+        };
+
+        assert_inferred(
+            adaptation_set,
+            ContentType::Audio,
+            "AdaptationSet@mimeType audio/mp4",
+        );
+    }
+
+    #[test]
+    fn content_type_from_representation_mime_type() {
+        let adaptation_set = representation(Representation {
+            mimeType: Some("application/mp4".to_owned()),
+            codecs: Some("wvtt".to_owned()),
+            ..Default::default()
+        });
+
+        assert_inferred(
+            adaptation_set,
+            ContentType::Text,
+            "Representation@mimeType application/mp4",
+        );
+    }
+
+    #[test]
+    fn content_type_from_codecs() {
+        let adaptation_set = AdaptationSet {
+            codecs: Some("avc1.64001f".to_owned()),
+            ..Default::default()
+        };
+
+        assert_inferred(adaptation_set, ContentType::Video, "@codecs avc1.64001f");
+    }
+
+    #[test]
+    fn content_type_from_dimensions() {
+        let adaptation_set = representation(Representation {
+            width: Some(1280),
+            ..Default::default()
+        });
+
+        assert_inferred(adaptation_set, ContentType::Video, "@width/@height");
+    }
+
+    #[test]
+    fn content_type_from_audio_sampling_rate() {
+        let adaptation_set = AdaptationSet {
+            audioSamplingRate: Some("48000".to_owned()),
+            ..Default::default()
+        };
+
+        assert_inferred(adaptation_set, ContentType::Audio, "@audioSamplingRate");
+    }
+
+    #[test]
+    fn content_type_not_inferred() {
+        let adaptation_set = AdaptationSet {
+            contentType: Some("unknown".to_owned()),
+            mimeType: Some("unknown/unknown".to_owned()),
+            ..Default::default()
+        };
+
+        let err = parse_content_type(&adaptation_set, &mut vec![]).unwrap_err();
+
+        assert!(matches!(err, ParseError::AdaptationSetWithoutContentType));
+        assert!(err.describe().contains(
+            "@contentType, then @mimeType, @codecs, @width, @height and @audioSamplingRate"
+        ));
+    }
 }
@@ -0,0 +1,245 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// The parts of a Segment Index Box (ISO/IEC 14496-12 8.16.3) needed to lay out subsegments
+#[derive(Debug)]
+pub struct Sidx {
+    pub timescale: u64,
+    pub earliest_presentation_time: u64,
+    pub subsegment_durations: Vec<u64>,
+}
+
+/// Parses a byte range in the `first-last` form used by @indexRange and @mediaRange
+pub fn parse_byte_range(range: &str) -> Result<(u64, u64), String> {
+    let (first, last) = range.split_once('-').ok_or(format!(
+        "Byte range {} is not in the form first-last",
+        range
+    ))?;
+
+    let first: u64 = first
+        .trim()
+        .parse()
+        .map_err(|err| format!("Invalid byte range start {}: {}", first, err))?;
+    let last: u64 = last
+        .trim()
+        .parse()
+        .map_err(|err| format!("Invalid byte range end {}: {}", last, err))?;
+
+    if last < first {
+        return Err(format!("Byte range {} ends before it starts", range));
+    }
+
+    Ok((first, last))
+}
+
+/// Reads the sidx box found at `index_range` within the file at `path`
+pub fn read_sidx(path: &Path, index_range: &str) -> Result<Sidx, String> {
+    let (first, last) = parse_byte_range(index_range)?;
+
+    let mut file = File::open(path).map_err(|err| format!("Unable to open {:?}: {}", path, err))?;
+
+    let length = file
+        .metadata()
+        .map_err(|err| format!("Unable to read the size of {:?}: {}", path, err))?
+        .len();
+
+    if last >= length {
+        return Err(format!(
+            "Index range {} is past the end of {:?} ({} bytes)",
+            index_range, path, length
+        ));
+    }
+
+    file.seek(SeekFrom::Start(first))
+        .map_err(|err| format!("Unable to seek to {} in {:?}: {}", first, path, err))?;
+
+    let mut buffer = vec![0u8; (last - first + 1) as usize];
+
+    file.read_exact(&mut buffer)
+        .map_err(|err| format!("Unable to read {} from {:?}: {}", index_range, path, err))?;
+
+    parse_sidx(&buffer)
+}
+
+fn parse_sidx(buffer: &[u8]) -> Result<Sidx, String> {
+    let mut reader = BoxReader {
+        buffer,
+        position: 0,
+    };
+
+    // The index range may start with other boxes (e.g. styp), walk until the sidx is found
+    loop {
+        let box_start = reader.position;
+        let size = reader.u32()? as usize;
+        let box_type = reader.bytes(4)?;
+
+        if box_type == b"sidx" {
+            break;
+        }
+
+        if size < 8 {
+            return Err(format!("Invalid box size {} before sidx", size));
+        }
+
+        reader.position = box_start + size;
+    }
+
+    let version = reader.bytes(1)?[0];
+    let _flags = reader.bytes(3)?;
+    let _reference_id = reader.u32()?;
+    let timescale = reader.u32()? as u64;
+
+    let earliest_presentation_time = match version {
+        0 => {
+            let ept = reader.u32()? as u64;
+            let _first_offset = reader.u32()?;
+            ept
+        }
+        _ => {
+            let ept = reader.u64()?;
+            let _first_offset = reader.u64()?;
+            ept
+        }
+    };
+
+    let _reserved = reader.bytes(2)?;
+    let reference_count = reader.bytes(2)?;
+    let reference_count = u16::from_be_bytes([reference_count[0], reference_count[1]]);
+
+    let mut subsegment_durations = vec![];
+
+    for _ in 0..reference_count {
+        // The top bit is reference_type, set when the reference is to another sidx
+        if reader.u32()? & 0x8000_0000 != 0 {
+            return Err(
+                "sidx references another sidx, hierarchical indexes are not supported".to_owned(),
+            );
+        }
+
+        let subsegment_duration = reader.u32()? as u64;
+        let _sap = reader.u32()?;
+
+        subsegment_durations.push(subsegment_duration);
+    }
+
+    if timescale == 0 {
+        return Err("sidx has a timescale of 0".to_owned());
+    }
+
+    Ok(Sidx {
+        timescale,
+        earliest_presentation_time,
+        subsegment_durations,
+    })
+}
+
+struct BoxReader<'a> {
+    buffer: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BoxReader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.position + count;
+
+        let bytes = self
+            .buffer
+            .get(self.position..end)
+            .ok_or("Unexpected end of index range while reading sidx".to_owned())?;
+
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.bytes(4)?;
+
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(((self.u32()? as u64) << 32) | self.u32()? as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A sidx box with a timescale of 1000, earliest presentation time 500 and a subsegment
+    /// per duration. References are to another sidx when `hierarchical`.
+    fn sidx(version: u8, durations: &[u32], hierarchical: bool) -> Vec<u8> {
+        let mut body = vec![version, 0, 0, 0];
+
+        body.extend(1u32.to_be_bytes());
+        body.extend(1000u32.to_be_bytes());
+
+        if version == 0 {
+            body.extend(500u32.to_be_bytes());
+            body.extend(0u32.to_be_bytes());
+        } else {
+            body.extend(500u64.to_be_bytes());
+            body.extend(0u64.to_be_bytes());
+        }
+
+        body.extend([0, 0]);
+        body.extend((durations.len() as u16).to_be_bytes());
+
+        for duration in durations {
+            let reference_type = if hierarchical { 0x8000_0000 } else { 0 };
+
+            body.extend((reference_type | 1024u32).to_be_bytes());
+            body.extend(duration.to_be_bytes());
+            body.extend(0x9000_0000u32.to_be_bytes());
+        }
+
+        let mut sidx = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+
+        sidx.extend(b"sidx");
+        sidx.extend(body);
+
+        sidx
+    }
+
+    #[test]
+    fn version_0() {
+        let sidx = parse_sidx(&sidx(0, &[2000, 2000, 1500], false)).unwrap();
+
+        assert_eq!(sidx.timescale, 1000);
+        assert_eq!(sidx.earliest_presentation_time, 500);
+        assert_eq!(sidx.subsegment_durations, vec![2000, 2000, 1500]);
+    }
+
+    #[test]
+    fn version_1_after_another_box() {
+        let mut buffer = vec![0, 0, 0, 8];
+
+        buffer.extend(b"styp");
+        buffer.extend(sidx(1, &[4000], false));
+
+        let sidx = parse_sidx(&buffer).unwrap();
+
+        assert_eq!(sidx.earliest_presentation_time, 500);
+        assert_eq!(sidx.subsegment_durations, vec![4000]);
+    }
+
+    #[test]
+    fn truncated() {
+        let buffer = sidx(0, &[2000, 2000], false);
+
+        assert!(parse_sidx(&buffer[..buffer.len() - 4])
+            .unwrap_err()
+            .contains("Unexpected end of index range"));
+    }
+
+    #[test]
+    fn hierarchical() {
+        assert!(parse_sidx(&sidx(0, &[2000], true))
+            .unwrap_err()
+            .contains("hierarchical"));
+    }
+}
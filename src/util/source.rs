@@ -0,0 +1,245 @@
+/// An element of the manifest source and the lines it spans, counting from 1
+#[derive(Debug)]
+pub struct SourceElement {
+    pub name: String,
+    pub id: Option<String>,
+    /// The start tag, for attributes that are not parsed up front
+    pub tag: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub children: Vec<SourceElement>,
+    /// The whole element, kept for the segment information dash-mpd does not parse on every
+    /// level. See `KEPT_ELEMENTS`.
+    pub source: Option<String>,
+    /// Where the start tag begins in the manifest
+    offset: usize,
+}
+
+impl SourceElement {
+    /// The child named `name` with the given @id, or failing that the one at `position` among
+    /// the children of that name. Elements without an @id are expanded as "No ID".
+    pub fn child(&self, name: &str, id: &str, position: usize) -> Option<&SourceElement> {
+        find_element(&self.children, name, id, position)
+    }
+
+    /// The first child named `name`, for elements that appear once, e.g. SegmentBase
+    pub fn first(&self, name: &str) -> Option<&SourceElement> {
+        self.children.iter().find(|element| element.name == name)
+    }
+
+    /// The value of the attribute `name` on the start tag
+    pub fn attribute(&self, name: &str) -> Option<String> {
+        attribute(&self.tag, name)
+    }
+}
+
+/// The element named `name` with the given @id, or failing that the one at `position`
+pub fn find_element<'a>(
+    elements: &'a [SourceElement],
+    name: &str,
+    id: &str,
+    position: usize,
+) -> Option<&'a SourceElement> {
+    let named = || elements.iter().filter(move |element| element.name == name);
+
+    named()
+        .find(|element| element.id.as_deref() == Some(id))
+        .or_else(|| named().nth(position))
+}
+
+// Only the elements the expanded model is made of are kept, with the segment information whose
+// attributes dash-mpd does not parse on every level
+const SCANNED_ELEMENTS: [&str; 5] = [
+    "Period",
+    "AdaptationSet",
+    "Representation",
+    "SegmentBase",
+    "SegmentList",
+];
+
+// Segment information is parsed again from its source where it is inherited
+const KEPT_ELEMENTS: [&str; 2] = ["SegmentBase", "SegmentList"];
+
+/// Finds the Period, AdaptationSet, Representation, SegmentBase and SegmentList elements of a
+/// manifest and the lines they span. This is a lenient scan rather than a parse, the manifest
+/// has already been parsed by the time the source is needed.
+pub fn scan_elements(xml: &str) -> Vec<SourceElement> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(xml.match_indices('\n').map(|(index, _)| index + 1))
+        .collect();
+
+    let line_of = |offset: usize| line_starts.partition_point(|start| *start <= offset);
+
+    let mut roots: Vec<SourceElement> = vec![];
+
+    // Every open element, with the scanned ones kept until they are closed
+    let mut stack: Vec<(String, Option<SourceElement>)> = vec![];
+
+    let mut position = 0;
+
+    while let Some(found) = xml[position..].find('<') {
+        let start = position + found;
+        let rest = &xml[start..];
+
+        let skip_to = |terminator: &str| {
+            rest.find(terminator)
+                .map_or(xml.len(), |end| start + end + terminator.len())
+        };
+
+        if rest.starts_with("<!--") {
+            position = skip_to("-->");
+            continue;
+        }
+
+        if rest.starts_with("<![CDATA[") {
+            position = skip_to("]]>");
+            continue;
+        }
+
+        if rest.starts_with("<?") {
+            position = skip_to("?>");
+            continue;
+        }
+
+        if rest.starts_with("<!") {
+            position = skip_to(">");
+            continue;
+        }
+
+        let end = start + tag_length(rest);
+        let tag = &xml[start..end];
+
+        position = end;
+
+        if let Some(name) = tag.strip_prefix("</") {
+            let name = local_name(name.trim_end_matches('>').trim());
+
+            // Unbalanced end tags are ignored
+            if let Some(open) = stack.iter().rposition(|(open, _)| open == name) {
+                while stack.len() > open {
+                    let (_, element) = stack.pop().expect("Element below the open one");
+
+                    if let Some(mut element) = element {
+                        element.end_line = line_of(end.saturating_sub(1));
+
+                        if KEPT_ELEMENTS.contains(&element.name.as_str()) {
+                            element.source = Some(xml[element.offset..end].to_owned());
+                        }
+
+                        attach(element, &mut stack, &mut roots);
+                    }
+                }
+            }
+
+            continue;
+        }
+
+        let name = local_name(
+            tag[1..]
+                .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+                .next()
+                .unwrap_or_default(),
+        )
+        .to_owned();
+
+        let element = SCANNED_ELEMENTS
+            .contains(&name.as_str())
+            .then(|| SourceElement {
+                name: name.clone(),
+                id: attribute(tag, "id"),
+                tag: tag.to_owned(),
+                start_line: line_of(start),
+                end_line: line_of(end.saturating_sub(1)),
+                children: vec![],
+                source: None,
+                offset: start,
+            });
+
+        if tag.ends_with("/>") {
+            if let Some(mut element) = element {
+                if KEPT_ELEMENTS.contains(&name.as_str()) {
+                    element.source = Some(tag.to_owned());
+                }
+
+                attach(element, &mut stack, &mut roots);
+            }
+        } else {
+            stack.push((name, element));
+        }
+    }
+
+    // Elements left open at the end of the document are kept as they are
+    while let Some((_, element)) = stack.pop() {
+        if let Some(element) = element {
+            attach(element, &mut stack, &mut roots);
+        }
+    }
+
+    roots
+}
+
+/// Adds a closed element to the closest scanned element that is still open
+fn attach(
+    element: SourceElement,
+    stack: &mut [(String, Option<SourceElement>)],
+    roots: &mut Vec<SourceElement>,
+) {
+    match stack.iter_mut().rev().find_map(|(_, open)| open.as_mut()) {
+        Some(parent) => parent.children.push(element),
+        None => roots.push(element),
+    }
+}
+
+/// The length of the tag at the start of `rest`, up to and including its closing >
+fn tag_length(rest: &str) -> usize {
+    let mut quote: Option<char> = None;
+
+    for (index, c) in rest.char_indices() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return index + 1,
+            _ => {}
+        }
+    }
+
+    rest.len()
+}
+
+/// Drops the namespace prefix, e.g. mpd:Period
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+
+    while let Some(found) = rest.find(name) {
+        let preceded_by_space = rest[..found]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_whitespace);
+
+        let after = rest[found + name.len()..].trim_start();
+
+        rest = &rest[found + name.len()..];
+
+        if !preceded_by_space {
+            continue;
+        }
+
+        let Some(value) = after.strip_prefix('=').map(str::trim_start) else {
+            continue;
+        };
+
+        let quote = value.chars().next()?;
+
+        if quote != '"' && quote != '\'' {
+            continue;
+        }
+
+        return value[1..].split(quote).next().map(|value| value.to_owned());
+    }
+
+    None
+}
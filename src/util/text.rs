@@ -0,0 +1,25 @@
+//! Formatting into a String. Unlike other `fmt::Write` targets writing to a String cannot fail,
+//! so the result is dropped.
+
+/// `write!` into a String
+#[macro_export]
+macro_rules! push_fmt {
+    ($text:expr, $($arg:tt)*) => {{
+        use std::fmt::Write as _;
+
+        let _ = write!($text, $($arg)*);
+    }};
+}
+
+/// `writeln!` into a String
+#[macro_export]
+macro_rules! push_line {
+    ($text:expr) => {
+        $text.push('\n')
+    };
+    ($text:expr, $($arg:tt)*) => {{
+        use std::fmt::Write as _;
+
+        let _ = writeln!($text, $($arg)*);
+    }};
+}
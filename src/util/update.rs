@@ -139,14 +139,17 @@ pub fn check_updates() {
         current_version, version
     );
 
-    let latest_asset = &body
+    let Some(latest_asset) = body
         .assets
         .iter()
         .find(|asset| asset.name.contains(target_arch))
-        .expect(&format!(
-            "Unable to match architecture {} to a download URL.",
+    else {
+        println!(
+            "Unable to match architecture {} to a download URL. Skipping update check.",
             target_arch
-        ));
+        );
+        return;
+    };
 
     if compare_versions(current_version, version) >= Ok(0) {
         println!("Current version is up to date");
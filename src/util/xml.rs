@@ -0,0 +1,8 @@
+/// Escapes text for use in XML content and double-quoted attributes, e.g. of SVG, HTML or JUnit
+/// reports
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}